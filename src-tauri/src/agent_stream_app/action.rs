@@ -0,0 +1,170 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::agent_stream_app;
+use crate::agent_stream_app::app::ASApp;
+
+const EMIT_SCREENSHOT: &str = "asapp:screenshot";
+const EMIT_SEARCH: &str = "asapp:search";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AppAction {
+    ShowMain,
+    HideMain,
+    ToggleMain,
+    ToggleFlow {
+        flow: String,
+    },
+    SendToInput {
+        flow: String,
+        node: String,
+        #[serde(default = "default_input_ch")]
+        ch: String,
+        #[serde(default)]
+        value: Value,
+    },
+    SendToAgent {
+        agent_id: String,
+        #[serde(default = "default_input_ch")]
+        ch: String,
+        #[serde(default)]
+        value: Value,
+    },
+    Screenshot,
+    ToggleFullscreen,
+    Search,
+    QuickInput,
+    OpenFlow {
+        flow: String,
+    },
+    RunFlow {
+        flow: String,
+        #[serde(default = "default_input_node")]
+        node: String,
+        #[serde(default = "default_input_ch")]
+        ch: String,
+        #[serde(default)]
+        input: Option<Value>,
+    },
+    ImportFlow {
+        path: String,
+    },
+}
+
+fn default_input_node() -> String {
+    "input".to_string()
+}
+
+fn default_input_ch() -> String {
+    "in".to_string()
+}
+
+pub fn run(app: &AppHandle, action: &AppAction) -> Result<()> {
+    log::debug!("run action: {:?}", action);
+    match action {
+        AppAction::ShowMain => agent_stream_app::window::show_main(app)?,
+        AppAction::HideMain => agent_stream_app::window::hide_main(app)?,
+        AppAction::ToggleMain => {
+            if agent_stream_app::window::is_main_visible(app)? {
+                agent_stream_app::window::hide_main(app)?;
+            } else {
+                agent_stream_app::window::show_main(app)?;
+            }
+        }
+        AppAction::ToggleFlow { flow } => {
+            let app = app.clone();
+            let flow = flow.clone();
+            tauri::async_runtime::spawn(async move {
+                let asapp = app.state::<ASApp>();
+                asapp.toggle_agent_flow(&flow).await.unwrap_or_else(|e| {
+                    log::error!("Failed to toggle agent flow {}: {}", flow, e);
+                });
+                agent_stream_app::tray::update(&app);
+            });
+        }
+        AppAction::SendToInput {
+            flow,
+            node,
+            ch,
+            value,
+        } => {
+            let app = app.clone();
+            let (flow, node, ch, value) = (flow.clone(), node.clone(), ch.clone(), value.clone());
+            tauri::async_runtime::spawn(async move {
+                agent_stream_app::app::send_flow_input(&app, &flow, &node, &ch, value)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::error!("Failed to send value to {}/{}: {}", flow, node, e);
+                    });
+            });
+        }
+        AppAction::SendToAgent {
+            agent_id,
+            ch,
+            value,
+        } => {
+            let app = app.clone();
+            let (agent_id, ch, value) = (agent_id.clone(), ch.clone(), value.clone());
+            tauri::async_runtime::spawn(async move {
+                agent_stream_app::app::send_input(&app, &agent_id, &ch, value)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::error!("Failed to send value to {}: {}", agent_id, e);
+                    });
+            });
+        }
+        AppAction::Screenshot => {
+            // The frontend toggles the screenshot-only view on this event.
+            agent_stream_app::window::focus_main(app)?;
+            app.emit_to("main", EMIT_SCREENSHOT, ())?;
+        }
+        AppAction::ToggleFullscreen => agent_stream_app::window::toggle_fullscreen(app)?,
+        AppAction::Search => {
+            agent_stream_app::window::focus_main(app)?;
+            app.emit_to("main", EMIT_SEARCH, ())?;
+        }
+        AppAction::QuickInput => agent_stream_app::quick_input::open(app)?,
+        AppAction::OpenFlow { flow } => agent_stream_app::window::show_main_flow(app, flow)?,
+        AppAction::RunFlow {
+            flow,
+            node,
+            ch,
+            input,
+        } => {
+            let app = app.clone();
+            let (flow, node, ch, input) = (flow.clone(), node.clone(), ch.clone(), input.clone());
+            tauri::async_runtime::spawn(async move {
+                let result = async {
+                    let asapp = app.state::<ASApp>();
+                    if !asapp.is_agent_flow_running(&flow) {
+                        asapp.start_agent_flow(&flow).await?;
+                        agent_stream_app::tray::update(&app);
+                    }
+                    if let Some(input) = input {
+                        agent_stream_app::app::send_flow_input(&app, &flow, &node, &ch, input)
+                            .await?;
+                    }
+                    anyhow::Ok(())
+                }
+                .await;
+                result.unwrap_or_else(|e| {
+                    log::error!("Failed to run agent flow {}: {}", flow, e);
+                });
+            });
+        }
+        AppAction::ImportFlow { path } => {
+            let flow = app.state::<ASApp>().import_agent_flow(path.clone())?;
+            agent_stream_app::tray::update(app);
+            agent_stream_app::window::show_main_flow(app, flow.name())?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn run_action_cmd(app: AppHandle, action: AppAction) -> Result<(), String> {
+    run(&app, &action).map_err(|e| e.to_string())
+}
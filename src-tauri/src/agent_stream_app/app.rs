@@ -1,24 +1,179 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::path::PathBuf;
+use std::sync::Mutex;
 
 use anyhow::{bail, Context as _, Result};
-use dirs;
 use tauri::{AppHandle, Manager, State};
 
-use agent_stream_kit::{ASKit, AgentFlow};
+use agent_stream_kit::{ASKit, AgentConfigs, AgentData, AgentFlow, AgentFlowNode};
 use askit_std_agents;
+use serde_json::Value;
 use tauri_plugin_askit::ASKitExt;
 
+use super::clipboard;
+use super::config_ref::ConfigRefs;
+use super::debugger::{self, Debugger};
+use super::fs_watch;
+use super::notification;
 use super::observer::ASAppObserver;
+use super::profile;
+use super::record::Recorder;
+use super::text_index::TextIndex;
+use super::trace::Tracer;
+use super::tray;
 
-static ASKIT_FLOWS_PATH: &'static str = ".askit/flows";
+const MAX_RECENT_ERRORS: usize = 10;
 
 pub struct ASApp {
     askit: ASKit,
+    flows_dir: Mutex<PathBuf>,
+    config_refs: ConfigRefs,
+    /// Node ids in the flow files by the ids they were given on load.
+    file_node_ids: Mutex<HashMap<String, String>>,
+    recent_errors: Mutex<VecDeque<RecentError>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecentError {
+    pub agent_id: String,
+    pub message: String,
 }
 
 impl ASApp {
     // AgentFlow
 
+    pub fn agent_flow_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.askit.get_agent_flows().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    // A flow is running while any of its nodes is enabled, as in the flow editor.
+    pub fn is_agent_flow_running(&self, name: &str) -> bool {
+        self.askit
+            .get_agent_flows()
+            .get(name)
+            .is_some_and(|flow| flow.nodes().iter().any(|n| n.enabled))
+    }
+
+    pub fn has_running_agent_flows(&self) -> bool {
+        self.askit
+            .get_agent_flows()
+            .values()
+            .any(|flow| flow.nodes().iter().any(|n| n.enabled))
+    }
+
+    pub async fn start_agent_flow(&self, name: &str) -> Result<()> {
+        self.set_agent_flow_enabled(name, true, &[]).await
+    }
+
+    /// Starts the flow, leaving the given nodes stopped.
+    pub async fn start_agent_flow_except(&self, name: &str, except: &[String]) -> Result<()> {
+        self.set_agent_flow_enabled(name, true, except).await
+    }
+
+    pub async fn stop_agent_flow(&self, name: &str) -> Result<()> {
+        self.set_agent_flow_enabled(name, false, &[]).await
+    }
+
+    async fn set_agent_flow_enabled(
+        &self,
+        name: &str,
+        enabled: bool,
+        except: &[String],
+    ) -> Result<()> {
+        let mut flow = self
+            .askit
+            .get_agent_flows()
+            .get(name)
+            .cloned()
+            .with_context(|| format!("Agent flow not found: {}", name))?;
+        let mut nodes = flow.nodes().to_vec();
+        for node in nodes
+            .iter_mut()
+            .filter(|n| n.enabled != enabled && !except.contains(&n.id))
+        {
+            if enabled {
+                self.askit.start_agent(&node.id).await?;
+            } else {
+                self.askit.stop_agent(&node.id).await?;
+            }
+            node.enabled = enabled;
+        }
+        flow.set_nodes(nodes);
+        self.askit.insert_agent_flow(flow)?;
+        Ok(())
+    }
+
+    pub async fn toggle_agent_flow(&self, name: &str) -> Result<bool> {
+        if self.is_agent_flow_running(name) {
+            self.stop_agent_flow(name).await?;
+            Ok(false)
+        } else {
+            self.start_agent_flow(name).await?;
+            Ok(true)
+        }
+    }
+
+    // Input
+
+    pub fn find_agent_node(&self, flow_name: &str, node: &str) -> Result<String> {
+        let flows = self.askit.get_agent_flows();
+        let flow = flows
+            .get(flow_name)
+            .with_context(|| format!("Agent flow not found: {}", flow_name))?;
+        let agent_id = flow
+            .nodes()
+            .iter()
+            .find(|n| n.id == node || n.title.as_deref() == Some(node))
+            .map(|n| n.id.clone())
+            .with_context(|| format!("Agent node not found: {} in {}", node, flow_name))?;
+        Ok(agent_id)
+    }
+
+    /// The id of the node in its saved flow file.
+    pub fn file_node_id(&self, agent_id: &str) -> String {
+        self.file_node_ids
+            .lock()
+            .unwrap()
+            .get(agent_id)
+            .cloned()
+            .unwrap_or_else(|| agent_id.to_string())
+    }
+
+    pub fn find_agent_node_by_file_id(&self, flow_name: &str, file_id: &str) -> Option<String> {
+        let flows = self.askit.get_agent_flows();
+        let flow = flows.get(flow_name)?;
+        flow.nodes()
+            .iter()
+            .find(|n| self.file_node_id(&n.id) == file_id)
+            .map(|n| n.id.clone())
+    }
+
+    pub async fn send_to_agent(&self, agent_id: &str, ch: &str, value: Value) -> Result<()> {
+        let data = AgentData::from_json_value(value)?;
+        self.askit
+            .agent_input(agent_id.to_string(), ch.to_string(), data)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn send_to_flow_input(
+        &self,
+        flow_name: &str,
+        node: &str,
+        ch: &str,
+        value: Value,
+    ) -> Result<()> {
+        let agent_id = self.find_agent_node(flow_name, node)?;
+        self.send_to_agent(&agent_id, ch, value).await
+    }
+
+    pub fn new_agent_flow(&self, name: &str) -> Result<AgentFlow> {
+        let flow = self.askit.new_agent_flow(name)?;
+        Ok(flow)
+    }
+
     pub async fn remove_agent_flow(&self, name: &str) -> Result<()> {
         self.askit.remove_agent_flow(name).await?;
 
@@ -47,8 +202,29 @@ impl ASApp {
         Ok(new_name.to_string())
     }
 
+    // Errors
+
+    pub fn push_error(&self, agent_id: &str, message: &str) {
+        let mut recent_errors = self.recent_errors.lock().unwrap();
+        if recent_errors.len() >= MAX_RECENT_ERRORS {
+            recent_errors.pop_front();
+        }
+        recent_errors.push_back(RecentError {
+            agent_id: agent_id.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    pub fn recent_errors(&self) -> Vec<RecentError> {
+        self.recent_errors.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn clear_errors(&self) {
+        self.recent_errors.lock().unwrap().clear();
+    }
+
     fn agent_flow_path(&self, flow_name: &str) -> Result<PathBuf> {
-        let mut flow_path = agent_flows_dir()?;
+        let mut flow_path = self.agent_flows_dir();
 
         let path_components: Vec<&str> = flow_name.split('/').collect();
         for &component in &path_components[..path_components.len()] {
@@ -69,14 +245,58 @@ impl ASApp {
             std::fs::create_dir_all(parent_path)?;
         }
 
-        let json = agent_flow.to_json()?;
+        // The file now has the current node ids
+        {
+            let mut file_node_ids = self.file_node_ids.lock().unwrap();
+            for node in agent_flow.nodes() {
+                file_node_ids.insert(node.id.clone(), node.id.clone());
+            }
+        }
+
+        // Keep the env and file references instead of their resolved values
+        let mut value = serde_json::to_value(&agent_flow)?;
+        for_each_node_configs(&mut value, |node_id, configs| {
+            self.config_refs.restore_configs(node_id, configs);
+        });
+        let json = serde_json::to_string_pretty(&value)?;
         std::fs::write(flow_path, json).with_context(|| "Failed to write agent flow file")?;
 
         Ok(())
     }
 
+    pub fn agent_flows_dir(&self) -> PathBuf {
+        self.flows_dir.lock().unwrap().clone()
+    }
+
+    pub async fn unload_agent_flows(&self) -> Result<()> {
+        for name in self.agent_flow_names() {
+            self.askit
+                .remove_agent_flow(&name)
+                .await
+                .with_context(|| format!("Failed to unload agent flow: {}", name))?;
+        }
+        self.config_refs.clear();
+        self.file_node_ids.lock().unwrap().clear();
+        Ok(())
+    }
+
+    pub fn load_agent_flows(&self, flows_dir: PathBuf) -> Result<()> {
+        *self.flows_dir.lock().unwrap() = flows_dir;
+        self.read_agent_flows_dir()?;
+        self.ensure_main_agent_flow();
+        Ok(())
+    }
+
+    fn ensure_main_agent_flow(&self) {
+        if self.askit.get_agent_flows().get("main").is_none() {
+            if let Err(e) = self.askit.new_agent_flow("main") {
+                log::error!("Failed to create main agent flow: {}", e);
+            };
+        }
+    }
+
     fn read_agent_flows_dir(&self) -> Result<()> {
-        let flows_dir = agent_flows_dir()?;
+        let flows_dir = self.agent_flows_dir();
         if !flows_dir.exists() {
             std::fs::create_dir_all(&flows_dir)
                 .with_context(|| "Failed to create flows directory")?;
@@ -168,11 +388,82 @@ impl ASApp {
         flow.set_name(base_name.clone());
 
         // Rename IDs in the flow
+        let file_ids: Vec<String> = flow.nodes().iter().map(|n| n.id.clone()).collect();
         let (nodes, edges) = self.askit.copy_sub_flow(flow.nodes(), flow.edges());
+        {
+            let mut file_node_ids = self.file_node_ids.lock().unwrap();
+            for (node, file_id) in nodes.iter().zip(file_ids) {
+                file_node_ids.insert(node.id.clone(), file_id);
+            }
+        }
         flow.set_nodes(nodes);
         flow.set_edges(edges);
 
-        Ok(flow)
+        // Resolve env and file references in the node configs
+        self.resolve_flow_configs(&flow)
+    }
+
+    fn resolve_flow_configs(&self, flow: &AgentFlow) -> Result<AgentFlow> {
+        let mut value = serde_json::to_value(flow)?;
+        for_each_node_configs(&mut value, |node_id, configs| {
+            self.config_refs.resolve_configs(node_id, configs);
+        });
+        serde_json::from_value(value).context("Failed to resolve agent flow configs")
+    }
+
+    // The frontend gets the env and file references instead of their resolved values,
+    // and the flows, nodes and configs it sends back are resolved again.
+    pub fn agent_flows_with_refs(&self) -> BTreeMap<String, Value> {
+        self.askit
+            .get_agent_flows()
+            .iter()
+            .filter_map(|(name, flow)| {
+                let mut value = serde_json::to_value(flow).ok()?;
+                for_each_node_configs(&mut value, |node_id, configs| {
+                    self.config_refs.restore_configs(node_id, configs);
+                });
+                Some((name.clone(), value))
+            })
+            .collect()
+    }
+
+    pub fn insert_agent_flow(&self, flow: &AgentFlow) -> Result<()> {
+        let flow = self.resolve_flow_configs(flow)?;
+        self.askit.insert_agent_flow(flow)?;
+        Ok(())
+    }
+
+    pub fn add_agent_flow_node(&self, flow_name: &str, node: &AgentFlowNode) -> Result<()> {
+        let mut value = serde_json::to_value(node)?;
+        if let Some(configs) = value.get_mut("configs") {
+            self.config_refs.resolve_configs(&node.id, configs);
+        }
+        let node: AgentFlowNode = serde_json::from_value(value)?;
+        self.askit.add_agent_flow_node(flow_name, &node)?;
+        Ok(())
+    }
+
+    pub async fn set_agent_configs(&self, agent_id: &str, mut configs: Value) -> Result<()> {
+        self.config_refs.resolve_configs(agent_id, &mut configs);
+        let configs: AgentConfigs = serde_json::from_value(configs)?;
+        self.askit
+            .set_agent_configs(agent_id.to_string(), configs)
+            .await?;
+        Ok(())
+    }
+}
+
+fn for_each_node_configs(flow: &mut Value, mut f: impl FnMut(&str, &mut Value)) {
+    let Some(nodes) = flow.get_mut("nodes").and_then(|v| v.as_array_mut()) else {
+        return;
+    };
+    for node in nodes {
+        let Some(node_id) = node.get("id").and_then(|v| v.as_str()).map(str::to_string) else {
+            continue;
+        };
+        if let Some(configs) = node.get_mut("configs") {
+            f(&node_id, configs);
+        }
     }
 }
 
@@ -183,21 +474,24 @@ pub fn init(app: &AppHandle) -> Result<()> {
     askit_cozodb_agents::register_agents(&askit);
     askit_lifelog::register_agents(&askit);
     askit_llm_agents::register_agents(&askit);
+    fs_watch::register_agents(&askit);
+    clipboard::register_agents(&askit);
+    notification::register_agents(&askit);
 
     let asapp = ASApp {
         askit: askit.clone(),
+        flows_dir: Mutex::new(profile::current_flows_dir(app)?),
+        config_refs: ConfigRefs::default(),
+        file_node_ids: Mutex::new(HashMap::new()),
+        recent_errors: Mutex::new(VecDeque::new()),
     };
     asapp.read_agent_flows_dir().unwrap_or_else(|e| {
         log::error!("Failed to read agent flows: {}", e);
     });
-
-    if asapp.askit.get_agent_flows().get("main").is_none() {
-        if let Err(e) = asapp.askit.new_agent_flow("main") {
-            log::error!("Failed to create main agent flow: {}", e);
-        };
-    }
+    asapp.ensure_main_agent_flow();
 
     app.manage(asapp);
+    tray::update(app);
 
     Ok(())
 }
@@ -212,37 +506,188 @@ pub async fn ready(app: &AppHandle) -> Result<()> {
 
 pub fn quit(_app: &AppHandle) {}
 
-fn agent_flows_dir() -> Result<PathBuf> {
-    let home_dir = dirs::home_dir().with_context(|| "Failed to get home directory")?;
-    let flows_dir = home_dir.join(ASKIT_FLOWS_PATH);
-    Ok(flows_dir)
+pub async fn send_input(app: &AppHandle, agent_id: &str, ch: &str, value: Value) -> Result<()> {
+    app.state::<Recorder>()
+        .record_input(app, agent_id, ch, &value);
+    app.state::<Tracer>()
+        .expect_input(app, agent_id, ch, &value);
+    app.state::<ASApp>()
+        .send_to_agent(agent_id, ch, value)
+        .await
+}
+
+pub async fn send_flow_input(
+    app: &AppHandle,
+    flow_name: &str,
+    node: &str,
+    ch: &str,
+    value: Value,
+) -> Result<()> {
+    let agent_id = app.state::<ASApp>().find_agent_node(flow_name, node)?;
+    send_input(app, &agent_id, ch, value).await
+}
+
+#[tauri::command]
+pub fn get_agent_flows_cmd(asapp: State<ASApp>) -> BTreeMap<String, Value> {
+    asapp.agent_flows_with_refs()
+}
+
+#[tauri::command]
+pub fn insert_agent_flow_cmd(asapp: State<ASApp>, agent_flow: AgentFlow) -> Result<(), String> {
+    asapp
+        .insert_agent_flow(&agent_flow)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_agent_flow_node_cmd(
+    asapp: State<ASApp>,
+    flow_name: String,
+    node: AgentFlowNode,
+) -> Result<(), String> {
+    asapp
+        .add_agent_flow_node(&flow_name, &node)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_agent_configs_cmd(
+    asapp: State<'_, ASApp>,
+    agent_id: String,
+    configs: Value,
+) -> Result<(), String> {
+    asapp
+        .set_agent_configs(&agent_id, configs)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn new_agent_flow_cmd(
+    app: AppHandle,
+    asapp: State<ASApp>,
+    name: String,
+) -> Result<AgentFlow, String> {
+    let flow = asapp.new_agent_flow(&name).map_err(|e| e.to_string())?;
+    tray::update(&app);
+    Ok(flow)
 }
 
 #[tauri::command]
-pub fn rename_agent_flow_cmd(
+pub async fn rename_agent_flow_cmd(
+    app: AppHandle,
     asapp: State<'_, ASApp>,
     old_name: String,
     new_name: String,
 ) -> Result<String, String> {
-    asapp
+    // Breakpoints refer to the flow by name
+    debugger::remove_flow_breakpoints(&app, &old_name)
+        .await
+        .map_err(|e| e.to_string())?;
+    let new_name = asapp
         .rename_agent_flow(&old_name, &new_name)
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    tray::update(&app);
+    Ok(new_name)
 }
 
 #[tauri::command]
-pub async fn remove_agent_flow_cmd(asapp: State<'_, ASApp>, name: String) -> Result<(), String> {
+pub async fn remove_agent_flow_cmd(
+    app: AppHandle,
+    asapp: State<'_, ASApp>,
+    name: String,
+) -> Result<(), String> {
     asapp
         .remove_agent_flow(&name)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    app.state::<Debugger>().forget_flow(&name);
+    tray::update(&app);
+    Ok(())
 }
 
 #[tauri::command]
-pub fn save_agent_flow_cmd(asapp: State<ASApp>, agent_flow: AgentFlow) -> Result<(), String> {
-    asapp.save_agent_flow(agent_flow).map_err(|e| e.to_string())
+pub fn save_agent_flow_cmd(
+    app: AppHandle,
+    asapp: State<ASApp>,
+    debugger: State<Debugger>,
+    recorder: State<Recorder>,
+    text_index: State<TextIndex>,
+    agent_flow: AgentFlow,
+) -> Result<(), String> {
+    let agent_flow = debugger
+        .restore_flow(agent_flow)
+        .and_then(|flow| recorder.restore_flow(flow))
+        .map_err(|e| e.to_string())?;
+    let flow_name = agent_flow.name().to_string();
+    asapp
+        .save_agent_flow(agent_flow)
+        .map_err(|e| e.to_string())?;
+    text_index.update_flow(&asapp.agent_flows_dir(), &flow_name);
+    tray::update(&app);
+    Ok(())
 }
 
 #[tauri::command]
-pub fn import_agent_flow_cmd(asapp: State<ASApp>, path: String) -> Result<AgentFlow, String> {
-    asapp.import_agent_flow(path).map_err(|e| e.to_string())
+pub fn import_agent_flow_cmd(
+    app: AppHandle,
+    asapp: State<ASApp>,
+    path: String,
+) -> Result<AgentFlow, String> {
+    let flow = asapp.import_agent_flow(path).map_err(|e| e.to_string())?;
+    tray::update(&app);
+    Ok(flow)
+}
+
+#[tauri::command]
+pub async fn start_agent_flow_cmd(
+    app: AppHandle,
+    asapp: State<'_, ASApp>,
+    name: String,
+) -> Result<(), String> {
+    asapp
+        .start_agent_flow(&name)
+        .await
+        .map_err(|e| e.to_string())?;
+    tray::update(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_agent_flow_cmd(
+    app: AppHandle,
+    asapp: State<'_, ASApp>,
+    name: String,
+) -> Result<(), String> {
+    asapp
+        .stop_agent_flow(&name)
+        .await
+        .map_err(|e| e.to_string())?;
+    tray::update(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn send_to_agent_cmd(
+    app: AppHandle,
+    agent_id: String,
+    ch: String,
+    value: Value,
+) -> Result<(), String> {
+    send_input(&app, &agent_id, &ch, value)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn send_to_flow_input_cmd(
+    app: AppHandle,
+    flow: String,
+    node: String,
+    ch: Option<String>,
+    value: Value,
+) -> Result<(), String> {
+    send_flow_input(&app, &flow, &node, ch.as_deref().unwrap_or("in"), value)
+        .await
+        .map_err(|e| e.to_string())
 }
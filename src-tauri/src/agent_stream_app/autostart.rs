@@ -7,6 +7,15 @@ use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
 use crate::agent_stream_app::settings::CoreSettings;
 
 pub fn init(app: &AppHandle) -> Result<()> {
+    app.plugin(tauri_plugin_autostart::init(
+        MacosLauncher::LaunchAgent,
+        None,
+    ))?;
+
+    update(app)
+}
+
+pub fn update(app: &AppHandle) -> Result<()> {
     let setting = app.state::<Mutex<CoreSettings>>();
     let is_autostart;
     {
@@ -14,11 +23,6 @@ pub fn init(app: &AppHandle) -> Result<()> {
         is_autostart = setting.autostart;
     }
 
-    app.plugin(tauri_plugin_autostart::init(
-        MacosLauncher::LaunchAgent,
-        None,
-    ))?;
-
     let autostart_manager = app.autolaunch();
 
     if is_autostart == Some(true) {
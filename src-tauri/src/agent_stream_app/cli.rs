@@ -0,0 +1,150 @@
+use anyhow::{Context as _, Result};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use super::action::{self, AppAction};
+use super::deep_link;
+
+const USAGE: &str = "\
+--send-input <flow> <node> <ch> <value>   send a value to a node of a flow
+--send-agent <agent_id> <ch> <value>      send a value to an agent
+--action <json>                           run an action
+askit://...                               open a deep link";
+
+/// Values are parsed as JSON, and taken as a string if they are not valid JSON.
+/// Unknown arguments, such as those added by the OS or a launcher, are skipped.
+pub fn parse_actions(args: &[String]) -> Result<Vec<AppAction>> {
+    let mut actions = Vec::new();
+    // Skip the program name
+    let mut args = args.iter().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut next = |name: &str| {
+            args.next()
+                .cloned()
+                .with_context(|| format!("Missing {} for {}\n{}", name, arg, USAGE))
+        };
+        match arg.as_str() {
+            "--send-input" => actions.push(AppAction::SendToInput {
+                flow: next("flow")?,
+                node: next("node")?,
+                ch: next("ch")?,
+                value: parse_value(&next("value")?),
+            }),
+            "--send-agent" => actions.push(AppAction::SendToAgent {
+                agent_id: next("agent_id")?,
+                ch: next("ch")?,
+                value: parse_value(&next("value")?),
+            }),
+            "--action" => {
+                let json = next("json")?;
+                let action = serde_json::from_str(&json)
+                    .with_context(|| format!("Invalid action: {}", json))?;
+                actions.push(action);
+            }
+            _ => log::warn!("Ignoring unknown argument: {}", arg),
+        }
+    }
+
+    Ok(actions)
+}
+
+pub fn parse_value(s: &str) -> Value {
+    serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.to_string()))
+}
+
+pub fn run_args(app: &AppHandle, args: &[String]) -> bool {
+    // Deep links are handled separately, since they come from other apps.
+    let (links, args): (Vec<String>, Vec<String>) = args
+        .iter()
+        .cloned()
+        .partition(|arg| deep_link::is_deep_link(arg));
+    for link in &links {
+        deep_link::handle_url(app, link).unwrap_or_else(|e| {
+            log::error!("Failed to handle deep link {}: {}", link, e);
+        });
+    }
+
+    let actions = match parse_actions(&args) {
+        Ok(actions) => actions,
+        Err(e) => {
+            log::error!("Failed to parse arguments: {}", e);
+            return !links.is_empty();
+        }
+    };
+    for action in &actions {
+        action::run(app, action).unwrap_or_else(|e| {
+            log::error!("Failed to run action {:?}: {}", action, e);
+        });
+    }
+    !actions.is_empty() || !links.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        std::iter::once("agent-stream-app")
+            .chain(args.iter().copied())
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn parse_send_input() {
+        let actions = parse_actions(&args(&["--send-input", "main", "input", "in", "42"])).unwrap();
+        assert_eq!(
+            actions,
+            vec![AppAction::SendToInput {
+                flow: "main".to_string(),
+                node: "input".to_string(),
+                ch: "in".to_string(),
+                value: Value::from(42),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_send_agent_with_string_value() {
+        let actions = parse_actions(&args(&["--send-agent", "a1", "in", "hello world"])).unwrap();
+        assert_eq!(
+            actions,
+            vec![AppAction::SendToAgent {
+                agent_id: "a1".to_string(),
+                ch: "in".to_string(),
+                value: Value::from("hello world"),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_action_json() {
+        let actions = parse_actions(&args(&["--action", r#"{"type":"show_main"}"#])).unwrap();
+        assert_eq!(actions, vec![AppAction::ShowMain]);
+    }
+
+    #[test]
+    fn skip_unknown_args() {
+        let actions = parse_actions(&args(&[
+            "--autostart",
+            "--action",
+            r#"{"type":"hide_main"}"#,
+            "/tmp/file.json",
+        ]))
+        .unwrap();
+        assert_eq!(actions, vec![AppAction::HideMain]);
+    }
+
+    #[test]
+    fn missing_value_is_an_error() {
+        assert!(parse_actions(&args(&["--send-agent", "a1", "in"])).is_err());
+        assert!(parse_actions(&args(&["--action", "not json"])).is_err());
+    }
+
+    #[test]
+    fn parse_value_falls_back_to_string() {
+        assert_eq!(parse_value("[1, 2]"), serde_json::json!([1, 2]));
+        assert_eq!(parse_value("abc"), Value::from("abc"));
+    }
+}
@@ -0,0 +1,446 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use agent_stream_kit::{
+    async_trait, new_agent_boxed, ASKit, AgentConfigEntry, AgentConfigs, AgentContext, AgentData,
+    AgentDefinition, AgentError, AgentValue, AsAgent, AsAgentData,
+};
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::{json, Value};
+
+const AGENT_KIND: &str = "agent";
+const CATEGORY: &str = "App";
+const CLIPBOARD_MONITOR_AGENT: &str = "asapp_clipboard_monitor";
+const CLIPBOARD_WRITE_AGENT: &str = "asapp_clipboard_write";
+
+const CH_TEXT: &str = "text";
+const CH_IMAGE: &str = "image";
+
+const CONFIG_MIN_INTERVAL_MS: &str = "min_interval_ms";
+const CONFIG_INCLUDE_IMAGES: &str = "include_images";
+const CONFIG_IGNORE_PASSWORDS: &str = "ignore_passwords";
+const CONFIG_GUESS_PASSWORDS: &str = "guess_passwords";
+const CONFIG_IGNORE_PATTERNS: &str = "ignore_patterns";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const DEFAULT_MIN_INTERVAL_MS: i64 = 1000;
+
+// The hash of the text put on the clipboard by the write agent, which monitors skip
+// so that a flow writing to the clipboard does not trigger itself.
+static WRITTEN_HASH: Mutex<Option<u64>> = Mutex::new(None);
+
+pub struct ClipboardMonitorAgent {
+    data: AsAgentData,
+    stop: Option<Arc<AtomicBool>>,
+}
+
+struct MonitorOptions {
+    min_interval: Duration,
+    include_images: bool,
+    ignore_passwords: bool,
+    guess_passwords: bool,
+    ignore_patterns: Vec<String>,
+}
+
+#[async_trait]
+impl AsAgent for ClipboardMonitorAgent {
+    fn new(
+        askit: ASKit,
+        id: String,
+        def_name: String,
+        config: Option<AgentConfigs>,
+    ) -> Result<Self, AgentError> {
+        Ok(Self {
+            data: AsAgentData::new(askit, id, def_name, config),
+            stop: None,
+        })
+    }
+
+    fn data(&self) -> &AsAgentData {
+        &self.data
+    }
+
+    fn mut_data(&mut self) -> &mut AsAgentData {
+        &mut self.data
+    }
+
+    async fn start(&mut self) -> Result<(), AgentError> {
+        let configs = self.configs()?;
+        let options = MonitorOptions {
+            min_interval: Duration::from_millis(
+                configs
+                    .get_integer(CONFIG_MIN_INTERVAL_MS)
+                    .unwrap_or(DEFAULT_MIN_INTERVAL_MS)
+                    .max(0) as u64,
+            ),
+            include_images: configs.get_bool(CONFIG_INCLUDE_IMAGES).unwrap_or(false),
+            ignore_passwords: configs.get_bool(CONFIG_IGNORE_PASSWORDS).unwrap_or(true),
+            guess_passwords: configs.get_bool(CONFIG_GUESS_PASSWORDS).unwrap_or(false),
+            ignore_patterns: configs
+                .get_string(CONFIG_IGNORE_PATTERNS)
+                .unwrap_or_default()
+                .lines()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        self.stop = Some(stop.clone());
+
+        let askit = self.askit().clone();
+        let agent_id = self.id().to_string();
+        // The clipboard is polled from a thread, since it is not available on every runtime thread.
+        std::thread::spawn(move || monitor(askit, agent_id, options, stop));
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), AgentError> {
+        if let Some(stop) = self.stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    async fn process(
+        &mut self,
+        _ctx: AgentContext,
+        _pin: String,
+        _data: AgentData,
+    ) -> Result<(), AgentError> {
+        Ok(())
+    }
+}
+
+enum ClipboardContent {
+    Text(String),
+    Image(arboard::ImageData<'static>),
+}
+
+impl ClipboardContent {
+    fn read(clipboard: &mut arboard::Clipboard, include_images: bool) -> Option<Self> {
+        if let Ok(text) = clipboard.get_text() {
+            if !text.is_empty() {
+                return Some(ClipboardContent::Text(text));
+            }
+        }
+        if include_images {
+            if let Ok(image) = clipboard.get_image() {
+                return Some(ClipboardContent::Image(image.to_owned_img()));
+            }
+        }
+        None
+    }
+
+    fn hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match self {
+            ClipboardContent::Text(text) => text.hash(&mut hasher),
+            ClipboardContent::Image(image) => image.bytes.hash(&mut hasher),
+        }
+        hasher.finish()
+    }
+}
+
+fn monitor(askit: ASKit, agent_id: String, options: MonitorOptions, stop: Arc<AtomicBool>) {
+    let mut clipboard = match arboard::Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            log::error!("Failed to open clipboard for {}: {}", agent_id, e);
+            return;
+        }
+    };
+
+    // Only changes after the start are emitted
+    let mut last_hash = ClipboardContent::read(&mut clipboard, options.include_images)
+        .map(|content| content.hash());
+    let mut last_emit: Option<Instant> = None;
+
+    while !stop.load(Ordering::Relaxed) {
+        std::thread::sleep(POLL_INTERVAL);
+
+        let Some(content) = ClipboardContent::read(&mut clipboard, options.include_images) else {
+            continue;
+        };
+        let hash = content.hash();
+        if last_hash == Some(hash) {
+            continue;
+        }
+        if is_written(hash) {
+            last_hash = Some(hash);
+            continue;
+        }
+        // Changes within the minimum interval are picked up on a later poll
+        if last_emit.is_some_and(|t| t.elapsed() < options.min_interval) {
+            continue;
+        }
+        last_hash = Some(hash);
+        if options.ignore_passwords && is_concealed() {
+            continue;
+        }
+
+        let (ch, value) = match &content {
+            ClipboardContent::Text(text) => {
+                if is_ignored(text, &options) {
+                    continue;
+                }
+                (CH_TEXT, Value::String(text.clone()))
+            }
+            ClipboardContent::Image(image) => match image_value(image) {
+                Ok(value) => (CH_IMAGE, value),
+                Err(e) => {
+                    log::error!("Failed to encode clipboard image: {}", e);
+                    continue;
+                }
+            },
+        };
+        last_emit = Some(Instant::now());
+
+        let data = match AgentData::from_json_value(value) {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!("Failed to convert clipboard contents: {}", e);
+                continue;
+            }
+        };
+        askit
+            .try_send_agent_out(agent_id.clone(), AgentContext::new(), ch.to_string(), data)
+            .unwrap_or_else(|e| {
+                log::error!("Failed to send clipboard contents from {}: {}", agent_id, e);
+            });
+    }
+}
+
+fn is_written(hash: u64) -> bool {
+    *WRITTEN_HASH.lock().unwrap() == Some(hash)
+}
+
+fn is_ignored(text: &str, options: &MonitorOptions) -> bool {
+    if options.guess_passwords && looks_like_password(text) {
+        return true;
+    }
+    options
+        .ignore_patterns
+        .iter()
+        .any(|pattern| text.contains(pattern.as_str()))
+}
+
+/// Whether the app that copied the contents marked them as secret, as password managers do.
+#[cfg(target_os = "macos")]
+fn is_concealed() -> bool {
+    use objc2_app_kit::NSPasteboard;
+    use objc2_foundation::NSString;
+
+    let concealed = NSString::from_str("org.nspasteboard.ConcealedType");
+    #[allow(unused_unsafe)]
+    let types = unsafe { NSPasteboard::generalPasteboard().types() };
+    types.is_some_and(|types| types.containsObject(&concealed))
+}
+
+#[cfg(windows)]
+fn is_concealed() -> bool {
+    clipboard_win::register_format("ExcludeClipboardContentFromMonitorProcessing")
+        .is_some_and(|format| clipboard_win::is_format_avail(format.get()))
+}
+
+#[cfg(target_os = "linux")]
+fn is_concealed() -> bool {
+    // The clipboard targets are listed with the tools of the display server.
+    let output = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        std::process::Command::new("wl-paste")
+            .arg("--list-types")
+            .output()
+    } else {
+        std::process::Command::new("xclip")
+            .args(["-selection", "clipboard", "-t", "TARGETS", "-o"])
+            .output()
+    };
+    output.is_ok_and(|output| {
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .any(|target| target.trim() == "x-kde-passwordManagerHint")
+    })
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+fn is_concealed() -> bool {
+    false
+}
+
+/// Password managers copy a single generated token, usually with several character classes.
+fn looks_like_password(text: &str) -> bool {
+    let len = text.chars().count();
+    if !(8..=128).contains(&len) || text.chars().any(char::is_whitespace) {
+        return false;
+    }
+    let classes = [
+        text.chars().any(|c| c.is_ascii_lowercase()),
+        text.chars().any(|c| c.is_ascii_uppercase()),
+        text.chars().any(|c| c.is_ascii_digit()),
+        text.chars().any(|c| c.is_ascii_punctuation()),
+    ];
+    let looks_like_url_or_path = text.contains("://") || text.starts_with('/');
+    classes.iter().filter(|c| **c).count() >= 3 && !looks_like_url_or_path
+}
+
+fn image_value(image: &arboard::ImageData) -> Result<Value> {
+    let mut png = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png, image.width as u32, image.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&image.bytes)?;
+    }
+    Ok(json!({
+        "width": image.width,
+        "height": image.height,
+        "data_url": format!("data:image/png;base64,{}", BASE64.encode(png)),
+    }))
+}
+
+pub struct ClipboardWriteAgent {
+    data: AsAgentData,
+}
+
+#[async_trait]
+impl AsAgent for ClipboardWriteAgent {
+    fn new(
+        askit: ASKit,
+        id: String,
+        def_name: String,
+        config: Option<AgentConfigs>,
+    ) -> Result<Self, AgentError> {
+        Ok(Self {
+            data: AsAgentData::new(askit, id, def_name, config),
+        })
+    }
+
+    fn data(&self) -> &AsAgentData {
+        &self.data
+    }
+
+    fn mut_data(&mut self) -> &mut AsAgentData {
+        &mut self.data
+    }
+
+    async fn process(
+        &mut self,
+        _ctx: AgentContext,
+        _pin: String,
+        data: AgentData,
+    ) -> Result<(), AgentError> {
+        let value =
+            serde_json::to_value(&data).map_err(|e| AgentError::InvalidValue(e.to_string()))?;
+        let value = value.get("value").cloned().unwrap_or(value);
+        let text = match value {
+            Value::String(text) => text,
+            value => serde_json::to_string_pretty(&value).unwrap_or_default(),
+        };
+
+        let mut clipboard =
+            arboard::Clipboard::new().map_err(|e| AgentError::IoError(e.to_string()))?;
+        // Recorded before writing, since the monitors poll from other threads
+        *WRITTEN_HASH.lock().unwrap() = Some(ClipboardContent::Text(text.clone()).hash());
+        clipboard
+            .set_text(text)
+            .map_err(|e| AgentError::IoError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+pub fn register_agents(askit: &ASKit) {
+    askit.register_agent(
+        AgentDefinition::new(
+            AGENT_KIND,
+            CLIPBOARD_MONITOR_AGENT,
+            Some(new_agent_boxed::<ClipboardMonitorAgent>),
+        )
+        .with_title("Clipboard Monitor")
+        .with_description("Emits text and images copied to the clipboard")
+        .with_category(CATEGORY)
+        .with_outputs(vec![CH_TEXT, CH_IMAGE])
+        .with_default_configs(vec![
+            (
+                CONFIG_MIN_INTERVAL_MS,
+                AgentConfigEntry::new(AgentValue::integer(DEFAULT_MIN_INTERVAL_MS), "integer")
+                    .with_title("Min Interval (ms)"),
+            ),
+            (
+                CONFIG_INCLUDE_IMAGES,
+                AgentConfigEntry::new(AgentValue::boolean(false), "boolean")
+                    .with_title("Include Images"),
+            ),
+            (
+                CONFIG_IGNORE_PASSWORDS,
+                AgentConfigEntry::new(AgentValue::boolean(true), "boolean")
+                    .with_title("Ignore Passwords")
+                    .with_description("Skip contents that password managers mark as secret"),
+            ),
+            (
+                CONFIG_GUESS_PASSWORDS,
+                AgentConfigEntry::new(AgentValue::boolean(false), "boolean")
+                    .with_title("Guess Passwords")
+                    .with_description("Also skip text that looks like a generated password"),
+            ),
+            (
+                CONFIG_IGNORE_PATTERNS,
+                AgentConfigEntry::new(AgentValue::string(""), "text")
+                    .with_title("Ignore Patterns")
+                    .with_description("Skip text containing any of these lines"),
+            ),
+        ]),
+    );
+
+    askit.register_agent(
+        AgentDefinition::new(
+            AGENT_KIND,
+            CLIPBOARD_WRITE_AGENT,
+            Some(new_agent_boxed::<ClipboardWriteAgent>),
+        )
+        .with_title("Clipboard Write")
+        .with_description("Puts the input on the clipboard")
+        .with_category(CATEGORY)
+        .with_inputs(vec![CH_TEXT]),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_like_password() {
+        assert!(looks_like_password("x7#Kq9!mZ2"));
+        assert!(looks_like_password("correcthorse42BATTERY"));
+    }
+
+    #[test]
+    fn test_is_written() {
+        let hash = ClipboardContent::Text("copied by a flow".to_string()).hash();
+        assert!(!is_written(hash));
+        *WRITTEN_HASH.lock().unwrap() = Some(hash);
+        assert!(is_written(hash));
+        assert!(!is_written(
+            ClipboardContent::Text("copied by the user".to_string()).hash()
+        ));
+    }
+
+    #[test]
+    fn test_does_not_look_like_password() {
+        // Too short, whitespace, or too few character classes
+        assert!(!looks_like_password("aB3!"));
+        assert!(!looks_like_password("Hello, World 123"));
+        assert!(!looks_like_password("lowercaseonly"));
+        assert!(!looks_like_password("12345678901"));
+        // URLs and paths
+        assert!(!looks_like_password("https://Example.com/a1"));
+        assert!(!looks_like_password("/Users/Me/file1.txt"));
+    }
+}
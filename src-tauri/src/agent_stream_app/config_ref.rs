@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::{bail, Context as _, Result};
+use serde_json::Value;
+
+const ENV_PREFIX: &str = "${env:";
+const FILE_PREFIX: &str = "${file:";
+
+/// Entries are keyed by an owner (agent name or node id) and config key,
+/// so that the raw reference can be written back when the configs are saved.
+#[derive(Default)]
+pub struct ConfigRefs(Mutex<HashMap<(String, String), ConfigRef>>);
+
+struct ConfigRef {
+    raw: String,
+    resolved: String,
+}
+
+impl ConfigRefs {
+    pub fn resolve_configs(&self, owner: &str, configs: &mut Value) {
+        let Some(configs) = configs.as_object_mut() else {
+            return;
+        };
+        let mut refs = self.0.lock().unwrap();
+        for (key, value) in configs.iter_mut() {
+            let Some(raw) = value.as_str().filter(|s| has_reference(s)) else {
+                continue;
+            };
+            match resolve(raw) {
+                Ok(resolved) => {
+                    refs.insert(
+                        (owner.to_string(), key.clone()),
+                        ConfigRef {
+                            raw: raw.to_string(),
+                            resolved: resolved.clone(),
+                        },
+                    );
+                    *value = Value::String(resolved);
+                }
+                Err(e) => {
+                    log::error!("Failed to resolve config {}.{}: {}", owner, key, e);
+                }
+            }
+        }
+    }
+
+    /// Put the raw references back for values that have not been changed since they were resolved.
+    pub fn restore_configs(&self, owner: &str, configs: &mut Value) {
+        let Some(configs) = configs.as_object_mut() else {
+            return;
+        };
+        let refs = self.0.lock().unwrap();
+        for (key, value) in configs.iter_mut() {
+            if let Some(config_ref) = refs.get(&(owner.to_string(), key.clone())) {
+                if value.as_str() == Some(config_ref.resolved.as_str()) {
+                    *value = Value::String(config_ref.raw.clone());
+                }
+            }
+        }
+    }
+
+    pub fn remove(&self, owner: &str, key: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .remove(&(owner.to_string(), key.to_string()));
+    }
+
+    pub fn remove_owner(&self, owner: &str) {
+        self.0.lock().unwrap().retain(|(o, _), _| o != owner);
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+pub fn has_reference(s: &str) -> bool {
+    s.contains(ENV_PREFIX) || s.contains(FILE_PREFIX)
+}
+
+pub fn resolve(s: &str) -> Result<String> {
+    let mut result = String::new();
+    let mut rest = s;
+
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let reference = &rest[start..];
+        let end = reference
+            .find('}')
+            .with_context(|| format!("Unterminated reference: {}", reference))?;
+        let inner = &reference[2..end];
+
+        if let Some(name) = inner.strip_prefix("env:") {
+            let value = std::env::var(name)
+                .with_context(|| format!("Environment variable not set: {}", name))?;
+            result.push_str(&value);
+        } else if let Some(path) = inner.strip_prefix("file:") {
+            let path = expand_home(path)?;
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read file: {:?}", path))?;
+            result.push_str(content.trim_end_matches(['\r', '\n']));
+        } else {
+            // Keep unknown references such as secrets as they are.
+            result.push_str(&reference[..=end]);
+        }
+
+        rest = &reference[end + 1..];
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+pub fn expand_home(path: &str) -> Result<PathBuf> {
+    if let Some(rest) = path.strip_prefix("~/") {
+        let Some(home_dir) = dirs::home_dir() else {
+            bail!("Failed to get home directory");
+        };
+        return Ok(home_dir.join(rest));
+    }
+    Ok(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_env() {
+        std::env::set_var("ASAPP_TEST_CONFIG_REF", "value");
+        assert_eq!(
+            resolve("a ${env:ASAPP_TEST_CONFIG_REF} b").unwrap(),
+            "a value b"
+        );
+        assert!(resolve("${env:ASAPP_TEST_CONFIG_REF_UNSET}").is_err());
+    }
+
+    #[test]
+    fn test_resolve_file() {
+        let path = std::env::temp_dir().join("asapp_test_config_ref.txt");
+        std::fs::write(&path, "secret\n").unwrap();
+        let s = format!("${{file:{}}}", path.to_string_lossy());
+        assert_eq!(resolve(&s).unwrap(), "secret");
+        std::fs::remove_file(&path).unwrap();
+        assert!(resolve(&s).is_err());
+    }
+
+    #[test]
+    fn test_resolve_keeps_unknown_references() {
+        assert_eq!(resolve("${secret:api_key}").unwrap(), "${secret:api_key}");
+        assert_eq!(resolve("no references").unwrap(), "no references");
+        assert!(resolve("${env:UNTERMINATED").is_err());
+    }
+
+    #[test]
+    fn test_restore_configs() {
+        std::env::set_var("ASAPP_TEST_CONFIG_REF_RESTORE", "resolved");
+        let refs = ConfigRefs::default();
+        let mut configs = json!({
+            "a": "${env:ASAPP_TEST_CONFIG_REF_RESTORE}",
+            "b": "${env:ASAPP_TEST_CONFIG_REF_RESTORE}",
+            "c": "plain",
+        });
+        refs.resolve_configs("agent", &mut configs);
+        assert_eq!(
+            configs,
+            json!({"a": "resolved", "b": "resolved", "c": "plain"})
+        );
+
+        // Changed values are saved as they are
+        configs["b"] = json!("changed");
+        refs.restore_configs("agent", &mut configs);
+        assert_eq!(
+            configs,
+            json!({"a": "${env:ASAPP_TEST_CONFIG_REF_RESTORE}", "b": "changed", "c": "plain"})
+        );
+
+        // References are kept by owner
+        let mut other = json!({"a": "resolved"});
+        refs.restore_configs("other", &mut other);
+        assert_eq!(other, json!({"a": "resolved"}));
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+
+use agent_stream_kit::{AgentData, AgentFlow};
+use anyhow::{bail, Context as _, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_askit::ASKitExt;
+
+use super::app::ASApp;
+use super::probe::{self, edge_field};
+
+const EMIT_DEBUG: &str = "asapp:debug";
+const PROBE_EDGE_SUFFIX: &str = "debug";
+
+/// A breakpoint moves the edges into the channel to a probe node, so that
+/// messages are held by the app until they are stepped or resumed.
+#[derive(Default)]
+pub struct Debugger(Mutex<DebuggerState>);
+
+#[derive(Default)]
+struct DebuggerState {
+    breakpoints: BTreeMap<String, Breakpoint>,
+    pending: VecDeque<PendingMessage>,
+    next_id: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Breakpoint {
+    pub flow: String,
+    pub agent_id: String,
+    pub ch: String,
+    #[serde(skip)]
+    probe_id: String,
+    /// The edges moved to the probe node, restored when the breakpoint is removed.
+    #[serde(skip)]
+    edges: Vec<Value>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PendingMessage {
+    pub id: u64,
+    pub flow: String,
+    pub agent_id: String,
+    pub ch: String,
+    pub data: AgentData,
+    pub time: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DebugState {
+    pub breakpoints: Vec<Breakpoint>,
+    pub pending: Vec<PendingMessage>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DebugEvent {
+    Paused { message: PendingMessage },
+    Edited { message: PendingMessage },
+    Delivered { id: u64 },
+}
+
+impl Debugger {
+    pub fn is_probe(&self, agent_id: &str) -> bool {
+        self.0.lock().unwrap().breakpoints.contains_key(agent_id)
+    }
+
+    pub fn hold(&self, app: &AppHandle, probe_id: &str, data: AgentData) {
+        let message = {
+            let mut state = self.0.lock().unwrap();
+            let Some(breakpoint) = state.breakpoints.get(probe_id).cloned() else {
+                return;
+            };
+            state.next_id += 1;
+            let message = PendingMessage {
+                id: state.next_id,
+                flow: breakpoint.flow,
+                agent_id: breakpoint.agent_id,
+                ch: breakpoint.ch,
+                data,
+                time: Utc::now(),
+            };
+            state.pending.push_back(message.clone());
+            message
+        };
+        log::debug!(
+            "paused at breakpoint: {}:{} in {}",
+            message.agent_id,
+            message.ch,
+            message.flow
+        );
+        emit_debug(app, &DebugEvent::Paused { message });
+    }
+
+    pub fn state(&self) -> DebugState {
+        let state = self.0.lock().unwrap();
+        DebugState {
+            breakpoints: state.breakpoints.values().cloned().collect(),
+            pending: state.pending.iter().cloned().collect(),
+        }
+    }
+
+    pub fn edit_pending(&self, app: &AppHandle, id: u64, value: Value) -> Result<()> {
+        let data = AgentData::from_json_value(value)?;
+        let message = {
+            let mut state = self.0.lock().unwrap();
+            let message = state
+                .pending
+                .iter_mut()
+                .find(|m| m.id == id)
+                .with_context(|| format!("Pending message not found: {}", id))?;
+            message.data = data;
+            message.clone()
+        };
+        emit_debug(app, &DebugEvent::Edited { message });
+        Ok(())
+    }
+
+    fn take_pending(&self, flow: &str, limit: usize) -> Vec<PendingMessage> {
+        let mut state = self.0.lock().unwrap();
+        let mut taken = Vec::new();
+        let mut kept = VecDeque::new();
+        for message in state.pending.drain(..) {
+            if message.flow == flow && taken.len() < limit {
+                taken.push(message);
+            } else {
+                kept.push_back(message);
+            }
+        }
+        state.pending = kept;
+        taken
+    }
+
+    pub fn restore_flow(&self, flow: AgentFlow) -> Result<AgentFlow> {
+        let state = self.0.lock().unwrap();
+        let mut value = serde_json::to_value(&flow)?;
+        let name = flow.name();
+        for breakpoint in state.breakpoints.values().filter(|b| b.flow == name) {
+            probe::strip_probe(&mut value, &breakpoint.probe_id, &breakpoint.edges);
+        }
+        serde_json::from_value(value).context("Failed to restore agent flow edges")
+    }
+
+    /// Drops the breakpoints and the held messages of the flow.
+    pub fn forget_flow(&self, flow: &str) -> Vec<Breakpoint> {
+        let mut state = self.0.lock().unwrap();
+        state.pending.retain(|m| m.flow != flow);
+        let probe_ids: Vec<String> = state
+            .breakpoints
+            .values()
+            .filter(|b| b.flow == flow)
+            .map(|b| b.probe_id.clone())
+            .collect();
+        probe_ids
+            .iter()
+            .filter_map(|probe_id| state.breakpoints.remove(probe_id))
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.breakpoints.clear();
+        state.pending.clear();
+    }
+}
+
+fn emit_debug(app: &AppHandle, event: &DebugEvent) {
+    app.emit(EMIT_DEBUG, event).unwrap_or_else(|e| {
+        log::error!("Failed to emit debug event: {}", e);
+    });
+}
+
+pub fn set_breakpoint(app: &AppHandle, flow_name: &str, node: &str, ch: &str) -> Result<()> {
+    let agent_id = app.state::<ASApp>().find_agent_node(flow_name, node)?;
+    let debugger = app.state::<Debugger>();
+    if debugger
+        .state()
+        .breakpoints
+        .iter()
+        .any(|b| b.agent_id == agent_id && b.ch == ch)
+    {
+        return Ok(());
+    }
+
+    let edges: Vec<Value> = probe::flow_edges(app, flow_name)?
+        .into_iter()
+        .filter(|edge| {
+            edge_field(edge, "target") == agent_id && edge_field(edge, "target_handle") == ch
+        })
+        .collect();
+    if edges.is_empty() {
+        bail!("No edges into {}:{} in {}", node, ch, flow_name);
+    }
+
+    let probe_id = probe::add_probe(app, flow_name)?;
+    for edge in &edges {
+        probe::redirect_edge(app, flow_name, edge, &probe_id, PROBE_EDGE_SUFFIX)?;
+    }
+
+    debugger.0.lock().unwrap().breakpoints.insert(
+        probe_id.clone(),
+        Breakpoint {
+            flow: flow_name.to_string(),
+            agent_id,
+            ch: ch.to_string(),
+            probe_id,
+            edges,
+        },
+    );
+    log::info!("set breakpoint: {}:{} in {}", node, ch, flow_name);
+
+    Ok(())
+}
+
+pub async fn remove_breakpoint(
+    app: &AppHandle,
+    flow_name: &str,
+    node: &str,
+    ch: &str,
+) -> Result<()> {
+    let agent_id = app.state::<ASApp>().find_agent_node(flow_name, node)?;
+    let breakpoint = {
+        let debugger = app.state::<Debugger>();
+        let mut state = debugger.0.lock().unwrap();
+        let probe_id = state
+            .breakpoints
+            .values()
+            .find(|b| b.flow == flow_name && b.agent_id == agent_id && b.ch == ch)
+            .map(|b| b.probe_id.clone())
+            .with_context(|| format!("Breakpoint not found: {}:{} in {}", node, ch, flow_name))?;
+        state.breakpoints.remove(&probe_id).unwrap()
+    };
+
+    for edge in &breakpoint.edges {
+        probe::restore_edge(app, flow_name, edge, PROBE_EDGE_SUFFIX)?;
+    }
+    probe::remove_probe(app, flow_name, &breakpoint.probe_id).await?;
+    log::info!("removed breakpoint: {}:{} in {}", node, ch, flow_name);
+
+    // Deliver what was held at this breakpoint
+    let pending: Vec<PendingMessage> = {
+        let debugger = app.state::<Debugger>();
+        let mut state = debugger.0.lock().unwrap();
+        let (held, kept): (Vec<_>, VecDeque<_>) = state
+            .pending
+            .drain(..)
+            .partition(|m| m.agent_id == breakpoint.agent_id && m.ch == breakpoint.ch);
+        state.pending = kept;
+        held
+    };
+    deliver(app, pending).await
+}
+
+/// Removes the probes of all breakpoints in the flow, without delivering the held messages.
+pub async fn remove_flow_breakpoints(app: &AppHandle, flow_name: &str) -> Result<()> {
+    let breakpoints = app.state::<Debugger>().forget_flow(flow_name);
+    for breakpoint in breakpoints {
+        for edge in &breakpoint.edges {
+            probe::restore_edge(app, flow_name, edge, PROBE_EDGE_SUFFIX)?;
+        }
+        probe::remove_probe(app, flow_name, &breakpoint.probe_id).await?;
+    }
+    Ok(())
+}
+
+pub async fn step(app: &AppHandle, flow_name: &str) -> Result<()> {
+    let pending = app.state::<Debugger>().take_pending(flow_name, 1);
+    deliver(app, pending).await
+}
+
+pub async fn resume(app: &AppHandle, flow_name: &str) -> Result<()> {
+    let pending = app.state::<Debugger>().take_pending(flow_name, usize::MAX);
+    deliver(app, pending).await
+}
+
+async fn deliver(app: &AppHandle, pending: Vec<PendingMessage>) -> Result<()> {
+    let askit = app.askit();
+    for message in pending {
+        askit
+            .agent_input(message.agent_id.clone(), message.ch.clone(), message.data)
+            .await?;
+        emit_debug(app, &DebugEvent::Delivered { id: message.id });
+    }
+    Ok(())
+}
+
+pub fn init(app: &AppHandle) -> Result<()> {
+    app.manage(Debugger::default());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_breakpoint_cmd(
+    app: AppHandle,
+    flow: String,
+    node: String,
+    ch: String,
+) -> Result<(), String> {
+    set_breakpoint(&app, &flow, &node, &ch).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_breakpoint_cmd(
+    app: AppHandle,
+    flow: String,
+    node: String,
+    ch: String,
+) -> Result<(), String> {
+    remove_breakpoint(&app, &flow, &node, &ch)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_debug_state_cmd(debugger: State<Debugger>) -> DebugState {
+    debugger.state()
+}
+
+#[tauri::command]
+pub fn edit_pending_message_cmd(
+    app: AppHandle,
+    debugger: State<Debugger>,
+    id: u64,
+    value: Value,
+) -> Result<(), String> {
+    debugger
+        .edit_pending(&app, id, value)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn debug_step_cmd(app: AppHandle, flow: String) -> Result<(), String> {
+    step(&app, &flow).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn debug_resume_cmd(app: AppHandle, flow: String) -> Result<(), String> {
+    resume(&app, &flow).await.map_err(|e| e.to_string())
+}
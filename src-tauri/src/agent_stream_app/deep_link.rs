@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::{bail, Context as _, Result};
+use percent_encoding::percent_decode_str;
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use super::action::{self, AppAction};
+use super::cli;
+use super::settings::CoreSettings;
+
+pub const SCHEME: &str = "askit";
+
+/// Parse a deep link into the action it triggers.
+///
+/// - `askit://flow/<name>` opens the flow
+/// - `askit://run/<name>?input=<value>&node=<node>&ch=<ch>` starts the flow and sends the input
+/// - `askit://import?path=<path>` imports a flow file
+pub fn parse_url(s: &str) -> Result<AppAction> {
+    let url = Url::parse(s).with_context(|| format!("Invalid URL: {}", s))?;
+    if url.scheme() != SCHEME {
+        bail!("Unsupported URL scheme: {}", s);
+    }
+    // Flow names may contain slashes
+    let name = percent_decode_str(url.path().trim_start_matches('/'))
+        .decode_utf8()?
+        .to_string();
+    let mut query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    let action = match url.host_str() {
+        Some("flow") if !name.is_empty() => AppAction::OpenFlow { flow: name },
+        Some("run") if !name.is_empty() => AppAction::RunFlow {
+            flow: name,
+            node: query.remove("node").unwrap_or_else(|| "input".to_string()),
+            ch: query.remove("ch").unwrap_or_else(|| "in".to_string()),
+            input: query.remove("input").map(|input| cli::parse_value(&input)),
+        },
+        Some("import") => AppAction::ImportFlow {
+            path: query
+                .remove("path")
+                .with_context(|| format!("Missing path: {}", s))?,
+        },
+        _ => bail!("Unknown deep link: {}", s),
+    };
+    Ok(action)
+}
+
+pub fn is_deep_link(arg: &str) -> bool {
+    arg.starts_with(&format!("{}://", SCHEME))
+}
+
+/// Run the action of a deep link. Since any web page can open them, run links must be
+/// allowed in the settings, and imports are confirmed by the user.
+pub fn handle_url(app: &AppHandle, url: &str) -> Result<()> {
+    log::info!("handle deep link: {}", url);
+    let action = parse_url(url)?;
+    if let AppAction::ImportFlow { path } = &action {
+        confirm_import(app, path, action.clone());
+        return Ok(());
+    }
+    if let AppAction::RunFlow { flow, .. } = &action {
+        let allowed = {
+            let settings = app.state::<Mutex<CoreSettings>>();
+            let settings = settings.lock().unwrap();
+            settings.allow_deep_link_run == Some(true)
+        };
+        if !allowed {
+            bail!(
+                "Deep links are not allowed to run flows ({}). Enable allow_deep_link_run in the settings.",
+                flow
+            );
+        }
+    }
+    action::run(app, &action)
+}
+
+fn confirm_import(app: &AppHandle, path: &str, action: AppAction) {
+    let app_handle = app.clone();
+    app.dialog()
+        .message(format!("A link asks to import the flow file:\n{}", path))
+        .title("Import Flow")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Import".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |confirmed| {
+            if !confirmed {
+                return;
+            }
+            action::run(&app_handle, &action).unwrap_or_else(|e| {
+                log::error!("Failed to import flow from deep link: {}", e);
+            });
+        });
+}
+
+pub fn init(app: &AppHandle) -> Result<()> {
+    app.plugin(tauri_plugin_deep_link::init())?;
+
+    // On Linux and Windows, links are passed as arguments and arrive through
+    // the single-instance callback. macOS sends them as events instead.
+    #[cfg(target_os = "macos")]
+    {
+        use tauri_plugin_deep_link::DeepLinkExt;
+
+        let app_handle = app.clone();
+        app.deep_link().on_open_url(move |event| {
+            for url in event.urls() {
+                handle_url(&app_handle, url.as_str()).unwrap_or_else(|e| {
+                    log::error!("Failed to handle deep link {}: {}", url, e);
+                });
+            }
+        });
+        // The link the app was launched with
+        for url in app.deep_link().get_current()?.unwrap_or_default() {
+            handle_url(app, url.as_str()).unwrap_or_else(|e| {
+                log::error!("Failed to handle deep link {}: {}", url, e);
+            });
+        }
+    }
+
+    // Installed bundles register the scheme, but development builds have to do it at runtime.
+    #[cfg(all(debug_assertions, any(target_os = "linux", windows)))]
+    {
+        use tauri_plugin_deep_link::DeepLinkExt;
+
+        app.deep_link().register_all()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_open_flow() {
+        assert_eq!(
+            parse_url("askit://flow/dir/my%20flow").unwrap(),
+            AppAction::OpenFlow {
+                flow: "dir/my flow".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_run_flow() {
+        assert_eq!(
+            parse_url("askit://run/flow?input=hello").unwrap(),
+            AppAction::RunFlow {
+                flow: "flow".to_string(),
+                node: "input".to_string(),
+                ch: "in".to_string(),
+                input: Some(cli::parse_value("hello")),
+            }
+        );
+        assert_eq!(
+            parse_url("askit://run/flow?node=start&ch=data").unwrap(),
+            AppAction::RunFlow {
+                flow: "flow".to_string(),
+                node: "start".to_string(),
+                ch: "data".to_string(),
+                input: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_import() {
+        assert_eq!(
+            parse_url("askit://import?path=%2Ftmp%2Fflow.json").unwrap(),
+            AppAction::ImportFlow {
+                path: "/tmp/flow.json".to_string()
+            }
+        );
+        assert!(parse_url("askit://import").is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse_url("https://flow/name").is_err());
+        assert!(parse_url("askit://flow/").is_err());
+        assert!(parse_url("askit://unknown/name").is_err());
+        assert!(parse_url("not a url").is_err());
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_askit::ASKitExt;
+
+use super::app::ASApp;
+use super::secret;
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct GlobalConfigKey {
+    pub agent: String,
+    pub key: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NodeRef {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FlowDependencies {
+    pub flow: String,
+    pub running: bool,
+    pub agents: BTreeMap<String, Vec<NodeRef>>,
+    pub global_configs: BTreeSet<GlobalConfigKey>,
+    pub missing_agents: BTreeSet<String>,
+    #[serde(skip)]
+    config_nodes: BTreeMap<GlobalConfigKey, Vec<NodeRef>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DependencyReport {
+    pub flows: Vec<FlowDependencies>,
+    pub agent_flows: BTreeMap<String, BTreeSet<String>>,
+    pub config_flows: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ImpactedFlow {
+    pub flow: String,
+    pub running: bool,
+    pub nodes: Vec<NodeRef>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConfigImpact {
+    pub configs: Vec<GlobalConfigKey>,
+    pub secret: bool,
+    pub flows: Vec<ImpactedFlow>,
+}
+
+fn global_config_keys(app: &AppHandle) -> BTreeMap<String, Vec<String>> {
+    let Ok(map) = serde_json::to_value(app.askit().get_global_configs_map()) else {
+        return BTreeMap::new();
+    };
+    map.as_object()
+        .into_iter()
+        .flatten()
+        .map(|(agent, configs)| {
+            let keys = configs
+                .as_object()
+                .map(|configs| configs.keys().cloned().collect())
+                .unwrap_or_default();
+            (agent.clone(), keys)
+        })
+        .collect()
+}
+
+/// A node overrides a global config when its own configs have a value for the key.
+fn overrides_global_config(node: &Value, key: &str) -> bool {
+    match node.get("configs").and_then(|configs| configs.get(key)) {
+        None | Some(Value::Null) => false,
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(_) => true,
+    }
+}
+
+fn flow_dependencies(app: &AppHandle) -> Vec<FlowDependencies> {
+    let asapp = app.state::<ASApp>();
+    let defs = serde_json::to_value(app.askit().get_agent_definitions()).unwrap_or_default();
+    let config_keys = global_config_keys(app);
+
+    let mut flows = Vec::new();
+    for (flow_name, flow) in app.askit().get_agent_flows() {
+        let Ok(flow) = serde_json::to_value(&flow) else {
+            continue;
+        };
+        let mut deps = FlowDependencies {
+            running: asapp.is_agent_flow_running(&flow_name),
+            flow: flow_name,
+            agents: BTreeMap::new(),
+            global_configs: BTreeSet::new(),
+            missing_agents: BTreeSet::new(),
+            config_nodes: BTreeMap::new(),
+        };
+        for node in flow
+            .get("nodes")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let field = |key: &str| node.get(key).and_then(Value::as_str).map(str::to_string);
+            let (Some(id), Some(def_name)) = (field("id"), field("def_name")) else {
+                continue;
+            };
+            if defs.get(&def_name).is_none() {
+                deps.missing_agents.insert(def_name.clone());
+            }
+            let node_ref = NodeRef {
+                id,
+                name: field("title").unwrap_or_else(|| def_name.clone()),
+                enabled: node
+                    .get("enabled")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            };
+            for key in config_keys.get(&def_name).into_iter().flatten() {
+                if overrides_global_config(node, key) {
+                    continue;
+                }
+                let config = GlobalConfigKey {
+                    agent: def_name.clone(),
+                    key: key.clone(),
+                };
+                deps.config_nodes
+                    .entry(config.clone())
+                    .or_default()
+                    .push(node_ref.clone());
+                deps.global_configs.insert(config);
+            }
+            deps.agents.entry(def_name).or_default().push(node_ref);
+        }
+        flows.push(deps);
+    }
+    flows.sort_by(|a, b| a.flow.cmp(&b.flow));
+    flows
+}
+
+pub fn dependency_report(app: &AppHandle) -> DependencyReport {
+    let flows = flow_dependencies(app);
+    let mut agent_flows: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut config_flows: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for deps in &flows {
+        for agent in deps.agents.keys() {
+            agent_flows
+                .entry(agent.clone())
+                .or_default()
+                .insert(deps.flow.clone());
+        }
+        for config in &deps.global_configs {
+            config_flows
+                .entry(format!("{}.{}", config.agent, config.key))
+                .or_default()
+                .insert(deps.flow.clone());
+        }
+    }
+    DependencyReport {
+        flows,
+        agent_flows,
+        config_flows,
+    }
+}
+
+/// Without `agent`, the key is looked up in every agent definition.
+pub fn config_impact(app: &AppHandle, agent: Option<&str>, key: &str) -> Result<ConfigImpact> {
+    let configs: Vec<GlobalConfigKey> = global_config_keys(app)
+        .into_iter()
+        .filter(|(name, _)| agent.is_none_or(|agent| agent == name))
+        .filter(|(_, keys)| keys.iter().any(|k| k == key))
+        .map(|(name, _)| GlobalConfigKey {
+            agent: name,
+            key: key.to_string(),
+        })
+        .collect();
+    if configs.is_empty() {
+        match agent {
+            Some(agent) => bail!("Global config not found: {}.{}", agent, key),
+            None => bail!("Global config not found: {}", key),
+        }
+    }
+
+    let secret_keys = secret::secret_config_keys(app);
+    let secret = configs
+        .iter()
+        .any(|c| secret_keys.contains(&(c.agent.clone(), c.key.clone())));
+
+    let flows = flow_dependencies(app)
+        .into_iter()
+        .filter_map(|deps| {
+            let nodes: Vec<NodeRef> = configs
+                .iter()
+                .flat_map(|c| deps.config_nodes.get(c).cloned().unwrap_or_default())
+                .collect();
+            if nodes.is_empty() {
+                return None;
+            }
+            Some(ImpactedFlow {
+                flow: deps.flow,
+                running: deps.running,
+                nodes,
+            })
+        })
+        .collect();
+
+    Ok(ConfigImpact {
+        configs,
+        secret,
+        flows,
+    })
+}
+
+#[tauri::command]
+pub fn get_dependency_report_cmd(app: AppHandle) -> DependencyReport {
+    dependency_report(&app)
+}
+
+#[tauri::command]
+pub fn get_config_impact_cmd(
+    app: AppHandle,
+    agent: Option<String>,
+    key: String,
+) -> Result<ConfigImpact, String> {
+    config_impact(&app, agent.as_deref(), &key).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_overrides_global_config() {
+        let node = json!({
+            "configs": {
+                "api_key": "sk-node",
+                "model": "",
+                "base_url": null,
+                "temperature": 0.5,
+            }
+        });
+        assert!(overrides_global_config(&node, "api_key"));
+        assert!(overrides_global_config(&node, "temperature"));
+        assert!(!overrides_global_config(&node, "model"));
+        assert!(!overrides_global_config(&node, "base_url"));
+        assert!(!overrides_global_config(&node, "missing"));
+        assert!(!overrides_global_config(&json!({}), "api_key"));
+    }
+}
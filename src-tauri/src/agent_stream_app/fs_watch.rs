@@ -0,0 +1,409 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use agent_stream_kit::{
+    async_trait, new_agent_boxed, ASKit, AgentConfigEntry, AgentConfigs, AgentContext, AgentData,
+    AgentDefinition, AgentError, AgentValue, AsAgent, AsAgentData,
+};
+use globset::{Glob, GlobMatcher};
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::json;
+
+use super::config_ref;
+
+const AGENT_KIND: &str = "agent";
+const CATEGORY: &str = "App";
+const FS_WATCH_AGENT: &str = "asapp_fs_watch";
+
+const CH_FILE: &str = "file";
+
+const CONFIG_PATHS: &str = "paths";
+const CONFIG_RECURSIVE: &str = "recursive";
+const CONFIG_INCLUDE_CONTENTS: &str = "include_contents";
+const CONFIG_MAX_CONTENTS_SIZE: &str = "max_contents_size";
+
+const DEFAULT_MAX_CONTENTS_SIZE: i64 = 1024 * 1024;
+
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+pub struct FsWatchAgent {
+    data: AsAgentData,
+    watcher: Option<RecommendedWatcher>,
+}
+
+#[derive(Clone)]
+struct WatchTarget {
+    dir: PathBuf,
+    /// Events may report the canonical path, such as /private/var for /var on macOS.
+    canonical_dir: Option<PathBuf>,
+    matcher: Option<GlobMatcher>,
+    /// Globs such as "**/*.pdf" match in subdirectories too.
+    recursive: bool,
+}
+
+impl WatchTarget {
+    fn matches(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.dir).ok().or_else(|| {
+            self.canonical_dir
+                .as_ref()
+                .and_then(|dir| path.strip_prefix(dir).ok())
+        });
+        let Some(relative) = relative else {
+            return false;
+        };
+        // The glob is written against the configured path
+        self.matcher
+            .as_ref()
+            .is_none_or(|m| m.is_match(self.dir.join(relative)))
+    }
+}
+
+#[async_trait]
+impl AsAgent for FsWatchAgent {
+    fn new(
+        askit: ASKit,
+        id: String,
+        def_name: String,
+        config: Option<AgentConfigs>,
+    ) -> Result<Self, AgentError> {
+        Ok(Self {
+            data: AsAgentData::new(askit, id, def_name, config),
+            watcher: None,
+        })
+    }
+
+    fn data(&self) -> &AsAgentData {
+        &self.data
+    }
+
+    fn mut_data(&mut self) -> &mut AsAgentData {
+        &mut self.data
+    }
+
+    async fn start(&mut self) -> Result<(), AgentError> {
+        let configs = self.configs()?;
+        let paths = configs.get_string(CONFIG_PATHS)?;
+        let recursive = configs.get_bool(CONFIG_RECURSIVE).unwrap_or(false);
+        let include_contents = configs.get_bool(CONFIG_INCLUDE_CONTENTS).unwrap_or(false);
+        let max_contents_size = configs
+            .get_integer(CONFIG_MAX_CONTENTS_SIZE)
+            .unwrap_or(DEFAULT_MAX_CONTENTS_SIZE)
+            .max(0) as u64;
+
+        let mut targets =
+            parse_targets(&paths).map_err(|e| AgentError::InvalidConfig(e.to_string()))?;
+        if targets.is_empty() {
+            return Ok(());
+        }
+        for target in &mut targets {
+            target.canonical_dir = std::fs::canonicalize(&target.dir)
+                .ok()
+                .filter(|dir| *dir != target.dir);
+        }
+        let matchers = targets.clone();
+
+        let askit = self.askit().clone();
+        let agent_id = self.id().to_string();
+        let (tx, rx) = mpsc::channel();
+        // The thread ends when the watcher, and with it the sender, is dropped.
+        std::thread::spawn(move || {
+            debounce_events(rx, |path, kind| {
+                let data = match file_event_data(path, kind, include_contents, max_contents_size) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        log::error!("Failed to read file event {:?}: {}", path, e);
+                        return;
+                    }
+                };
+                askit
+                    .try_send_agent_out(
+                        agent_id.clone(),
+                        AgentContext::new(),
+                        CH_FILE.to_string(),
+                        data,
+                    )
+                    .unwrap_or_else(|e| {
+                        log::error!("Failed to send file event from {}: {}", agent_id, e);
+                    });
+            });
+        });
+
+        let agent_id = self.id().to_string();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        log::error!("File watch error in {}: {}", agent_id, e);
+                        return;
+                    }
+                };
+                let kind = match event.kind {
+                    EventKind::Create(_) => "create",
+                    EventKind::Modify(ModifyKind::Metadata(_)) => return,
+                    EventKind::Modify(ModifyKind::Name(_)) => "rename",
+                    EventKind::Modify(_) => "modify",
+                    EventKind::Remove(_) => "delete",
+                    _ => return,
+                };
+                for path in event.paths {
+                    if matchers.iter().any(|target| target.matches(&path)) {
+                        let _ = tx.send((path, kind));
+                    }
+                }
+            })
+            .map_err(|e| AgentError::IoError(e.to_string()))?;
+
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        for target in &targets {
+            let mode = if target.recursive {
+                RecursiveMode::Recursive
+            } else {
+                mode
+            };
+            watcher
+                .watch(&target.dir, mode)
+                .map_err(|e| AgentError::IoError(format!("{:?}: {}", target.dir, e)))?;
+        }
+        self.watcher = Some(watcher);
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), AgentError> {
+        // Dropping the watcher stops it
+        self.watcher = None;
+        Ok(())
+    }
+
+    async fn process(
+        &mut self,
+        _ctx: AgentContext,
+        _pin: String,
+        _data: AgentData,
+    ) -> Result<(), AgentError> {
+        Ok(())
+    }
+}
+
+/// Merges the bursts of events that editors and copies cause.
+#[derive(Default)]
+struct Debouncer {
+    pending: Vec<(PathBuf, &'static str, Instant)>,
+}
+
+impl Debouncer {
+    fn push(&mut self, path: PathBuf, kind: &'static str, now: Instant) {
+        match self.pending.iter_mut().find(|(p, _, _)| *p == path) {
+            Some(event) => {
+                // A new file is still new after it is written.
+                if !(event.1 == "create" && kind == "modify") {
+                    event.1 = kind;
+                }
+                event.2 = now;
+            }
+            None => self.pending.push((path, kind, now)),
+        }
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.pending
+            .iter()
+            .map(|(_, _, last)| *last + DEBOUNCE)
+            .min()
+    }
+
+    /// Takes the paths that have been quiet for `DEBOUNCE`.
+    fn take_quiet(&mut self, now: Instant) -> Vec<(PathBuf, &'static str)> {
+        let mut quiet = Vec::new();
+        self.pending.retain(|(path, kind, last)| {
+            if now.duration_since(*last) < DEBOUNCE {
+                return true;
+            }
+            quiet.push((path.clone(), *kind));
+            false
+        });
+        quiet
+    }
+}
+
+fn debounce_events(rx: Receiver<(PathBuf, &'static str)>, mut emit: impl FnMut(&Path, &str)) {
+    let mut debouncer = Debouncer::default();
+    loop {
+        let received = match debouncer.deadline() {
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok((path, kind)) => debouncer.push(path, kind, Instant::now()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        for (path, kind) in debouncer.take_quiet(Instant::now()) {
+            emit(&path, kind);
+        }
+    }
+}
+
+fn parse_targets(paths: &str) -> anyhow::Result<Vec<WatchTarget>> {
+    let mut targets = Vec::new();
+    for pattern in paths
+        .split(['\n', ','])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let pattern = config_ref::expand_home(pattern)?;
+        let pattern_str = pattern.to_string_lossy();
+        if !pattern_str.contains(['*', '?', '[', '{']) {
+            targets.push(WatchTarget {
+                dir: pattern,
+                canonical_dir: None,
+                matcher: None,
+                recursive: false,
+            });
+            continue;
+        }
+
+        // Watch the directory before the first component with a glob character
+        let mut dir = PathBuf::new();
+        for component in Path::new(pattern_str.as_ref()).components() {
+            if component
+                .as_os_str()
+                .to_string_lossy()
+                .contains(['*', '?', '[', '{'])
+            {
+                break;
+            }
+            dir.push(component);
+        }
+        targets.push(WatchTarget {
+            dir,
+            canonical_dir: None,
+            matcher: Some(Glob::new(&pattern_str)?.compile_matcher()),
+            recursive: pattern_str.contains("**"),
+        });
+    }
+    Ok(targets)
+}
+
+fn file_event_data(
+    path: &Path,
+    kind: &str,
+    include_contents: bool,
+    max_size: u64,
+) -> anyhow::Result<AgentData> {
+    let size = std::fs::metadata(path).ok().map(|m| m.len());
+    let contents = if include_contents && kind != "delete" && size.is_some_and(|s| s <= max_size) {
+        // Binary files have no text contents
+        std::fs::read_to_string(path).ok()
+    } else {
+        None
+    };
+    let value = json!({
+        "path": path.to_string_lossy(),
+        "kind": kind,
+        "size": size,
+        "contents": contents,
+    });
+    Ok(AgentData::from_json_value(value)?)
+}
+
+pub fn register_agents(askit: &ASKit) {
+    askit.register_agent(
+        AgentDefinition::new(
+            AGENT_KIND,
+            FS_WATCH_AGENT,
+            Some(new_agent_boxed::<FsWatchAgent>),
+        )
+        .with_title("File Watch")
+        .with_description("Emits file create, modify, rename and delete events")
+        .with_category(CATEGORY)
+        .with_outputs(vec![CH_FILE])
+        .with_default_configs(vec![
+            (
+                CONFIG_PATHS,
+                AgentConfigEntry::new(AgentValue::string(""), "text")
+                    .with_title("Paths")
+                    .with_description("Directories or glob patterns, one per line"),
+            ),
+            (
+                CONFIG_RECURSIVE,
+                AgentConfigEntry::new(AgentValue::boolean(false), "boolean")
+                    .with_title("Recursive"),
+            ),
+            (
+                CONFIG_INCLUDE_CONTENTS,
+                AgentConfigEntry::new(AgentValue::boolean(false), "boolean")
+                    .with_title("Include Contents"),
+            ),
+            (
+                CONFIG_MAX_CONTENTS_SIZE,
+                AgentConfigEntry::new(AgentValue::integer(DEFAULT_MAX_CONTENTS_SIZE), "integer")
+                    .with_title("Max Contents Size"),
+            ),
+        ]),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debouncer() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::default();
+        debouncer.push(PathBuf::from("a"), "create", start);
+        debouncer.push(PathBuf::from("a"), "modify", start);
+        debouncer.push(PathBuf::from("b"), "modify", start);
+        debouncer.push(PathBuf::from("b"), "modify", start);
+        debouncer.push(PathBuf::from("c"), "modify", start);
+        debouncer.push(PathBuf::from("c"), "rename", start);
+        assert_eq!(debouncer.deadline(), Some(start + DEBOUNCE));
+        assert!(debouncer.take_quiet(start).is_empty());
+
+        // An event on "b" keeps it pending
+        let later = start + DEBOUNCE / 2;
+        debouncer.push(PathBuf::from("b"), "delete", later);
+        assert_eq!(
+            debouncer.take_quiet(start + DEBOUNCE),
+            vec![
+                (PathBuf::from("a"), "create"),
+                (PathBuf::from("c"), "rename")
+            ]
+        );
+        assert_eq!(debouncer.deadline(), Some(later + DEBOUNCE));
+        assert_eq!(
+            debouncer.take_quiet(later + DEBOUNCE),
+            vec![(PathBuf::from("b"), "delete")]
+        );
+        assert_eq!(debouncer.deadline(), None);
+    }
+
+    #[test]
+    fn test_watch_target_matches() {
+        let targets = parse_targets("/tmp/docs\n/tmp/pdfs/**/*.pdf").unwrap();
+        assert_eq!(targets[0].dir, PathBuf::from("/tmp/docs"));
+        assert!(!targets[0].recursive);
+        assert!(targets[0].matches(Path::new("/tmp/docs/a.txt")));
+        assert!(!targets[0].matches(Path::new("/tmp/other/a.txt")));
+
+        assert_eq!(targets[1].dir, PathBuf::from("/tmp/pdfs"));
+        assert!(targets[1].recursive);
+        assert!(targets[1].matches(Path::new("/tmp/pdfs/sub/a.pdf")));
+        assert!(!targets[1].matches(Path::new("/tmp/pdfs/sub/a.txt")));
+        assert!(!targets[1].matches(Path::new("/private/tmp/pdfs/a.pdf")));
+
+        let mut target = targets[1].clone();
+        target.canonical_dir = Some(PathBuf::from("/private/tmp/pdfs"));
+        assert!(target.matches(Path::new("/private/tmp/pdfs/a.pdf")));
+        assert!(!target.matches(Path::new("/private/tmp/pdfs/a.txt")));
+    }
+}
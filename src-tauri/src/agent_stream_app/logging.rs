@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+use crate::agent_stream_app::settings::CoreSettings;
+
+const LOG_FILE_NAME: &str = "agent-stream-app";
+const MAX_LOG_FILE_SIZE: u128 = 10 * 1024 * 1024;
+const DEFAULT_QUERY_LIMIT: usize = 1000;
+const RETENTION_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub const AGENT_LOG_TARGET: &str = "askit::agent";
+
+/// Log levels by target. The empty target is the default level.
+static LOG_LEVELS: LazyLock<RwLock<HashMap<String, log::LevelFilter>>> =
+    LazyLock::new(|| RwLock::new(default_log_levels()));
+
+fn default_log_levels() -> HashMap<String, log::LevelFilter> {
+    let mut levels = HashMap::new();
+    levels.insert(String::new(), log::LevelFilter::Info);
+    levels.insert(
+        "agent_stream_app_lib".to_string(),
+        if cfg!(debug_assertions) {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Info
+        },
+    );
+    levels
+}
+
+fn level_for(target: &str) -> log::LevelFilter {
+    let levels = LOG_LEVELS.read().unwrap();
+    levels
+        .iter()
+        .filter(|(prefix, _)| {
+            prefix.is_empty()
+                || target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        })
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, level)| *level)
+        .unwrap_or(log::LevelFilter::Info)
+}
+
+pub fn builder() -> tauri_plugin_log::Builder {
+    tauri_plugin_log::Builder::new()
+        // Levels are filtered at runtime with the levels in the settings.
+        .level(log::LevelFilter::Trace)
+        .filter(|metadata| metadata.level() <= level_for(metadata.target()))
+        .clear_targets()
+        .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(TargetKind::LogDir {
+                file_name: Some(LOG_FILE_NAME.to_string()),
+            })
+            .format(|out, message, record| {
+                let entry = LogEntry {
+                    time: Utc::now(),
+                    level: record.level().to_string(),
+                    target: record.target().to_string(),
+                    message: message.to_string(),
+                };
+                out.finish(format_args!(
+                    "{}",
+                    serde_json::to_string(&entry).unwrap_or_default()
+                ))
+            }),
+        ])
+        .rotation_strategy(RotationStrategy::KeepAll)
+        .max_file_size(MAX_LOG_FILE_SIZE)
+}
+
+/// Remove old log files periodically, so that a long-running app is pruned too.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(RETENTION_CHECK_INTERVAL).await;
+            let retention_days = app
+                .state::<Mutex<CoreSettings>>()
+                .lock()
+                .unwrap()
+                .log_retention_days;
+            if let Some(days) = retention_days {
+                remove_old_logs(&app, days).unwrap_or_else(|e| {
+                    log::error!("Failed to remove old log files: {}", e);
+                });
+            }
+        }
+    });
+}
+
+pub fn update(app: &AppHandle) -> Result<()> {
+    let (log_levels, retention_days) = {
+        let settings = app.state::<Mutex<CoreSettings>>();
+        let settings = settings.lock().unwrap();
+        (settings.log_levels.clone(), settings.log_retention_days)
+    };
+
+    let mut levels = default_log_levels();
+    for (target, level) in log_levels.iter().flatten() {
+        levels.insert(target.clone(), parse_level(level)?);
+    }
+    *LOG_LEVELS.write().unwrap() = levels;
+
+    if let Some(days) = retention_days {
+        remove_old_logs(app, days)?;
+    }
+
+    Ok(())
+}
+
+pub fn parse_level(level: &str) -> Result<log::LevelFilter> {
+    log::LevelFilter::from_str(level).with_context(|| format!("Invalid log level: {}", level))
+}
+
+fn log_files(app: &AppHandle) -> Result<Vec<std::path::PathBuf>> {
+    let log_dir = app.path().app_log_dir()?;
+    if !log_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(&log_dir)? {
+        let path = entry?.path();
+        let is_log = path.extension().unwrap_or_default() == "log"
+            && path
+                .file_stem()
+                .is_some_and(|stem| stem.to_string_lossy().starts_with(LOG_FILE_NAME));
+        if is_log {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn remove_old_logs(app: &AppHandle, retention_days: u32) -> Result<()> {
+    let retention = Duration::from_secs(u64::from(retention_days) * 24 * 60 * 60);
+    let now = SystemTime::now();
+    for path in log_files(app)? {
+        let modified = std::fs::metadata(&path)?.modified()?;
+        if now.duration_since(modified).unwrap_or_default() > retention {
+            log::info!("remove old log file: {:?}", path);
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub time: DateTime<Utc>,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct LogQuery {
+    pub level: Option<String>,
+    pub target: Option<String>,
+    pub agent_id: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+pub fn query_logs(app: &AppHandle, query: &LogQuery) -> Result<Vec<LogEntry>> {
+    let level = query.level.as_deref().map(parse_level).transpose()?;
+    let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+
+    let mut entries = Vec::new();
+    for path in log_files(app)? {
+        // Files last written before `since` have no entries to return.
+        if let Some(since) = query.since {
+            let modified = std::fs::metadata(&path)?.modified()?;
+            if DateTime::<Utc>::from(modified) < since {
+                continue;
+            }
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read log file: {:?}", path))?;
+        for line in content.lines() {
+            // Lines written before the JSON format was introduced are skipped.
+            let Ok(entry) = serde_json::from_str::<LogEntry>(line) else {
+                continue;
+            };
+            if let Some(level) = level {
+                match log::Level::from_str(&entry.level) {
+                    Ok(entry_level) if entry_level <= level => {}
+                    _ => continue,
+                }
+            }
+            if let Some(target) = &query.target {
+                if !entry.target.starts_with(target.as_str()) {
+                    continue;
+                }
+            }
+            if let Some(agent_id) = &query.agent_id {
+                if !entry.message.contains(agent_id.as_str()) {
+                    continue;
+                }
+            }
+            if query.since.is_some_and(|since| entry.time < since)
+                || query.until.is_some_and(|until| entry.time > until)
+            {
+                continue;
+            }
+            entries.push(entry);
+        }
+    }
+
+    entries.sort_by_key(|entry| entry.time);
+    if entries.len() > limit {
+        entries.drain(..entries.len() - limit);
+    }
+
+    Ok(entries)
+}
+
+#[tauri::command]
+pub fn query_logs_cmd(app: AppHandle, query: Option<LogQuery>) -> Result<Vec<LogEntry>, String> {
+    query_logs(&app, &query.unwrap_or_default()).map_err(|e| e.to_string())
+}
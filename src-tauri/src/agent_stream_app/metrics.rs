@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::settings::CoreSettings;
+use super::topology::Topology;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct Metrics {
+    state: Mutex<MetricsState>,
+    topology: Mutex<Topology>,
+    server: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+}
+
+#[derive(Default)]
+struct MetricsState {
+    agents: HashMap<String, AgentMetrics>,
+    pending_inputs: HashMap<String, Instant>,
+    started: Option<Instant>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AgentMetrics {
+    pub inputs: BTreeMap<String, u64>,
+    pub displays: u64,
+    pub errors: u64,
+    pub latency: LatencyStats,
+}
+
+/// Time from an input to the next display or output of the agent.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LatencyStats {
+    pub count: u64,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+    pub last_ms: f64,
+}
+
+impl LatencyStats {
+    fn record(&mut self, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        self.count += 1;
+        self.total_ms += ms;
+        self.mean_ms = self.total_ms / self.count as f64;
+        self.max_ms = self.max_ms.max(ms);
+        self.last_ms = ms;
+    }
+
+    fn merge(&mut self, other: &LatencyStats) {
+        self.count += other.count;
+        self.total_ms += other.total_ms;
+        if self.count > 0 {
+            self.mean_ms = self.total_ms / self.count as f64;
+        }
+        self.max_ms = self.max_ms.max(other.max_ms);
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct FlowMetrics {
+    pub inputs: u64,
+    pub displays: u64,
+    pub errors: u64,
+    pub latency: LatencyStats,
+    /// Inputs per second since the metrics were started or reset.
+    pub throughput: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MetricsReport {
+    pub agents: BTreeMap<String, AgentMetrics>,
+    pub flows: BTreeMap<String, FlowMetrics>,
+}
+
+impl Metrics {
+    pub fn record_input(&self, app: &AppHandle, agent_id: &str, ch: &str) {
+        let now = Instant::now();
+
+        // An input of this agent is an output of the agents connected to it.
+        let sources: Vec<String> = {
+            let mut topology = self.topology.lock().unwrap();
+            topology.refresh(app);
+            topology
+                .in_edges(agent_id)
+                .iter()
+                .map(|edge| edge.source.clone())
+                .collect()
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.started.get_or_insert(now);
+        for source in sources {
+            state.complete(&source, now);
+        }
+
+        let metrics = state.agents.entry(agent_id.to_string()).or_default();
+        *metrics.inputs.entry(ch.to_string()).or_default() += 1;
+        state.pending_inputs.insert(agent_id.to_string(), now);
+    }
+
+    pub fn record_display(&self, agent_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .agents
+            .entry(agent_id.to_string())
+            .or_default()
+            .displays += 1;
+        state.complete(agent_id, Instant::now());
+    }
+
+    pub fn record_error(&self, agent_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.agents.entry(agent_id.to_string()).or_default().errors += 1;
+        state.pending_inputs.remove(agent_id);
+    }
+
+    pub fn report(&self, app: &AppHandle) -> MetricsReport {
+        let mut topology = self.topology.lock().unwrap();
+        topology.refresh(app);
+        let state = self.state.lock().unwrap();
+
+        let mut flows: BTreeMap<String, FlowMetrics> = BTreeMap::new();
+        for (agent_id, metrics) in &state.agents {
+            let Some(flow_name) = topology.flow_of(agent_id) else {
+                continue;
+            };
+            let flow = flows.entry(flow_name.clone()).or_default();
+            flow.inputs += metrics.inputs.values().sum::<u64>();
+            flow.displays += metrics.displays;
+            flow.errors += metrics.errors;
+            flow.latency.merge(&metrics.latency);
+        }
+        let elapsed = state
+            .started
+            .map(|started| started.elapsed().as_secs_f64())
+            .unwrap_or_default();
+        if elapsed > 0.0 {
+            for flow in flows.values_mut() {
+                flow.throughput = flow.inputs as f64 / elapsed;
+            }
+        }
+
+        MetricsReport {
+            agents: state
+                .agents
+                .iter()
+                .map(|(id, m)| (id.clone(), m.clone()))
+                .collect(),
+            flows,
+        }
+    }
+
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.agents.clear();
+        state.pending_inputs.clear();
+        state.started = None;
+    }
+}
+
+impl MetricsState {
+    fn complete(&mut self, agent_id: &str, now: Instant) {
+        if let Some(started) = self.pending_inputs.remove(agent_id) {
+            self.agents
+                .entry(agent_id.to_string())
+                .or_default()
+                .latency
+                .record(now - started);
+        }
+    }
+}
+
+impl MetricsReport {
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# TYPE askit_agent_inputs_total counter");
+        for (agent_id, metrics) in &self.agents {
+            for (ch, count) in &metrics.inputs {
+                let _ = writeln!(
+                    out,
+                    "askit_agent_inputs_total{{agent_id=\"{}\",ch=\"{}\"}} {}",
+                    escape_label(agent_id),
+                    escape_label(ch),
+                    count
+                );
+            }
+        }
+
+        let counters: [(&str, fn(&AgentMetrics) -> f64); 4] = [
+            ("askit_agent_displays_total", |m| m.displays as f64),
+            ("askit_agent_errors_total", |m| m.errors as f64),
+            ("askit_agent_latency_ms_sum", |m| m.latency.total_ms),
+            ("askit_agent_latency_ms_count", |m| m.latency.count as f64),
+        ];
+        for (name, value) in counters {
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for (agent_id, metrics) in &self.agents {
+                let _ = writeln!(
+                    out,
+                    "{}{{agent_id=\"{}\"}} {}",
+                    name,
+                    escape_label(agent_id),
+                    value(metrics)
+                );
+            }
+        }
+
+        let flow_counters: [(&str, fn(&FlowMetrics) -> f64); 5] = [
+            ("askit_flow_inputs_total", |m| m.inputs as f64),
+            ("askit_flow_displays_total", |m| m.displays as f64),
+            ("askit_flow_errors_total", |m| m.errors as f64),
+            ("askit_flow_latency_ms_sum", |m| m.latency.total_ms),
+            ("askit_flow_latency_ms_count", |m| m.latency.count as f64),
+        ];
+        for (name, value) in flow_counters {
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for (flow_name, metrics) in &self.flows {
+                let _ = writeln!(
+                    out,
+                    "{}{{flow=\"{}\"}} {}",
+                    name,
+                    escape_label(flow_name),
+                    value(metrics)
+                );
+            }
+        }
+
+        let _ = writeln!(out, "# TYPE askit_flow_inputs_per_second gauge");
+        for (flow_name, metrics) in &self.flows {
+            let _ = writeln!(
+                out,
+                "askit_flow_inputs_per_second{{flow=\"{}\"}} {}",
+                escape_label(flow_name),
+                metrics.throughput
+            );
+        }
+
+        out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub fn init(app: &AppHandle) -> Result<()> {
+    app.manage(Metrics::default());
+    update(app)
+}
+
+pub fn update(app: &AppHandle) -> Result<()> {
+    let port = {
+        let settings = app.state::<Mutex<CoreSettings>>();
+        let settings = settings.lock().unwrap();
+        settings.metrics_port
+    };
+
+    let metrics = app.state::<Metrics>();
+    let mut server = metrics.server.lock().unwrap();
+    if let Some(handle) = server.take() {
+        handle.abort();
+    }
+
+    if let Some(port) = port.filter(|port| *port != 0) {
+        // Bind here so that an unavailable port is reported to the caller.
+        let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        log::info!("metrics endpoint: http://127.0.0.1:{}/metrics", port);
+        let app = app.clone();
+        *server = Some(tauri::async_runtime::spawn(async move {
+            match TcpListener::from_std(listener) {
+                Ok(listener) => serve(app, listener).await,
+                Err(e) => log::error!("Failed to start metrics endpoint: {}", e),
+            }
+        }));
+    }
+
+    Ok(())
+}
+
+async fn serve(app: AppHandle, listener: TcpListener) {
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::error!("Failed to accept metrics connection: {}", e);
+                continue;
+            }
+        };
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = respond(&app, stream).await {
+                log::error!("Failed to write metrics response: {}", e);
+            }
+        });
+    }
+}
+
+async fn respond(app: &AppHandle, mut stream: TcpStream) -> std::io::Result<()> {
+    // The request is not inspected; every path returns the metrics.
+    let mut buf = [0u8; 1024];
+    let _ = tokio::time::timeout(READ_TIMEOUT, stream.read(&mut buf)).await;
+
+    let body = app.state::<Metrics>().report(app).to_prometheus();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await
+}
+
+#[tauri::command]
+pub fn get_metrics_cmd(app: AppHandle, metrics: State<Metrics>) -> MetricsReport {
+    metrics.report(&app)
+}
+
+#[tauri::command]
+pub fn reset_metrics_cmd(metrics: State<Metrics>) {
+    metrics.reset();
+}
@@ -1,7 +1,29 @@
+pub mod action;
 pub mod app;
 pub mod autostart;
+pub mod cli;
+pub mod clipboard;
+pub mod config_ref;
+pub mod debugger;
+pub mod deep_link;
+pub mod dependency;
+pub mod fs_watch;
+pub mod logging;
+pub mod metrics;
+pub mod notification;
 pub mod observer;
+pub mod probe;
+pub mod profile;
+pub mod quick_input;
+pub mod record;
+pub mod schedule;
+pub mod search;
+pub mod secret;
 pub mod settings;
 pub mod shortcut;
+pub mod text_index;
+pub mod topology;
+pub mod trace;
+pub mod transfer;
 pub mod tray;
 pub mod window;
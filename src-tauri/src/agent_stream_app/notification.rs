@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use agent_stream_kit::{
+    async_trait, new_agent_boxed, ASKit, AgentConfigEntry, AgentConfigs, AgentContext, AgentData,
+    AgentDefinition, AgentError, AgentValue, AsAgent, AsAgentData,
+};
+use anyhow::Result;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use super::settings::{CoreSettings, DEFAULT_ERROR_NOTIFICATION_INTERVAL_SECS};
+use super::tray::truncate;
+
+const AGENT_KIND: &str = "agent";
+const CATEGORY: &str = "App";
+const NOTIFICATION_AGENT: &str = "asapp_notification";
+
+const CH_DATA: &str = "data";
+
+const CONFIG_TITLE: &str = "title";
+const CONFIG_BODY: &str = "body";
+
+const DEFAULT_TITLE: &str = "Agent Stream App";
+const DEFAULT_BODY: &str = "{{value}}";
+const MAX_BODY_LEN: usize = 256;
+
+/// Agents only have the ASKit, so the notification agent reaches the app through this.
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+#[derive(Default)]
+struct ErrorNotifications(Mutex<HashMap<String, Instant>>);
+
+pub fn init(app: &AppHandle) -> Result<()> {
+    APP_HANDLE.set(app.clone()).ok();
+    app.manage(ErrorNotifications::default());
+    Ok(())
+}
+
+fn show(app: &AppHandle, title: &str, body: &str) -> Result<()> {
+    app.notification()
+        .builder()
+        .title(title)
+        .body(truncate(body, MAX_BODY_LEN))
+        .show()?;
+    Ok(())
+}
+
+pub fn notify_error(app: &AppHandle, agent_id: &str, message: &str) {
+    let interval = {
+        let settings = app.state::<Mutex<CoreSettings>>();
+        let settings = settings.lock().unwrap();
+        if settings.notify_errors != Some(true) {
+            return;
+        }
+        Duration::from_secs(
+            settings
+                .error_notification_interval_secs
+                .unwrap_or(DEFAULT_ERROR_NOTIFICATION_INTERVAL_SECS),
+        )
+    };
+
+    {
+        let Some(notifications) = app.try_state::<ErrorNotifications>() else {
+            return;
+        };
+        let mut last_notified = notifications.0.lock().unwrap();
+        let now = Instant::now();
+        if last_notified
+            .get(agent_id)
+            .is_some_and(|last| now.duration_since(*last) < interval)
+        {
+            return;
+        }
+        last_notified.insert(agent_id.to_string(), now);
+    }
+
+    show(app, "Agent Error", &format!("{}: {}", agent_id, message)).unwrap_or_else(|e| {
+        log::error!("Failed to show error notification: {}", e);
+    });
+}
+
+/// Replace `{{value}}` with the whole input and `{{key.path}}` with a field of it.
+fn render_template(template: &str, value: &Value) -> String {
+    let mut result = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let path = rest[start + 2..start + end].trim();
+        let field = if path == "value" {
+            Some(value)
+        } else {
+            path.split('.').try_fold(value, |v, key| v.get(key))
+        };
+        match field {
+            Some(Value::String(s)) => result.push_str(s),
+            Some(Value::Null) | None => {}
+            Some(v) => result.push_str(&v.to_string()),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+pub struct NotificationAgent {
+    data: AsAgentData,
+}
+
+#[async_trait]
+impl AsAgent for NotificationAgent {
+    fn new(
+        askit: ASKit,
+        id: String,
+        def_name: String,
+        config: Option<AgentConfigs>,
+    ) -> Result<Self, AgentError> {
+        Ok(Self {
+            data: AsAgentData::new(askit, id, def_name, config),
+        })
+    }
+
+    fn data(&self) -> &AsAgentData {
+        &self.data
+    }
+
+    fn mut_data(&mut self) -> &mut AsAgentData {
+        &mut self.data
+    }
+
+    async fn process(
+        &mut self,
+        _ctx: AgentContext,
+        _pin: String,
+        data: AgentData,
+    ) -> Result<(), AgentError> {
+        let configs = self.configs()?;
+        let title = configs
+            .get_string(CONFIG_TITLE)
+            .unwrap_or_else(|_| DEFAULT_TITLE.to_string());
+        let body = configs
+            .get_string(CONFIG_BODY)
+            .unwrap_or_else(|_| DEFAULT_BODY.to_string());
+
+        let value =
+            serde_json::to_value(&data).map_err(|e| AgentError::InvalidValue(e.to_string()))?;
+        let value = value.get("value").cloned().unwrap_or(value);
+
+        let Some(app) = APP_HANDLE.get() else {
+            return Ok(());
+        };
+        show(
+            app,
+            &render_template(&title, &value),
+            &render_template(&body, &value),
+        )
+        .map_err(|e| AgentError::IoError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+pub fn register_agents(askit: &ASKit) {
+    askit.register_agent(
+        AgentDefinition::new(
+            AGENT_KIND,
+            NOTIFICATION_AGENT,
+            Some(new_agent_boxed::<NotificationAgent>),
+        )
+        .with_title("Notification")
+        .with_description("Shows the input as a desktop notification")
+        .with_category(CATEGORY)
+        .with_inputs(vec![CH_DATA])
+        .with_default_configs(vec![
+            (
+                CONFIG_TITLE,
+                AgentConfigEntry::new(AgentValue::string(DEFAULT_TITLE), "string")
+                    .with_title("Title")
+                    .with_description("{{value}} or {{key.path}} are replaced with the input"),
+            ),
+            (
+                CONFIG_BODY,
+                AgentConfigEntry::new(AgentValue::string(DEFAULT_BODY), "text").with_title("Body"),
+            ),
+        ]),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_template() {
+        let value = json!({"title": "Done", "result": {"count": 3, "ok": true}, "none": null});
+        assert_eq!(
+            render_template("{{title}}: {{ result.count }} {{result.ok}}", &value),
+            "Done: 3 true"
+        );
+        assert_eq!(render_template("[{{none}}{{missing.key}}]", &value), "[]");
+        assert_eq!(
+            render_template("no placeholders", &value),
+            "no placeholders"
+        );
+    }
+
+    #[test]
+    fn test_render_template_value() {
+        assert_eq!(render_template("got {{value}}", &json!("text")), "got text");
+        assert_eq!(render_template("{{value}}", &json!([1, 2])), "[1,2]");
+    }
+
+    #[test]
+    fn test_render_template_unclosed() {
+        assert_eq!(render_template("a {{b", &json!({})), "a {{b");
+    }
+}
@@ -1,7 +1,17 @@
 use agent_stream_kit::{ASKitEvent, ASKitObserver, AgentData};
 use anyhow::{Context as _, Result};
 use serde::Serialize;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::app::ASApp;
+use super::debugger::Debugger;
+use super::logging::AGENT_LOG_TARGET;
+use super::metrics::Metrics;
+use super::notification;
+use super::record::Recorder;
+use super::text_index::TextIndex;
+use super::trace::Tracer;
+use super::tray;
 
 const EMIT_DISPLAY: &str = "askit:display";
 const EMIT_ERROR: &str = "askit:error";
@@ -66,6 +76,58 @@ impl ASAppObserver {
 
 impl ASKitObserver for ASAppObserver {
     fn notify(&self, event: &ASKitEvent) {
+        // Messages held at a breakpoint arrive as displays of its probe node.
+        if let ASKitEvent::AgentDisplay(agent_id, _, data) = event {
+            if let Some(debugger) = self.app.try_state::<Debugger>() {
+                if debugger.is_probe(agent_id) {
+                    debugger.hold(&self.app, agent_id, data.clone());
+                    return;
+                }
+            }
+            // Outputs of source nodes being recorded arrive at their taps the same way.
+            if let Some(recorder) = self.app.try_state::<Recorder>() {
+                if recorder.is_tap(agent_id) {
+                    recorder.tap(&self.app, agent_id, data.clone());
+                    return;
+                }
+            }
+        }
+
+        if let Some(metrics) = self.app.try_state::<Metrics>() {
+            match event {
+                ASKitEvent::AgentDisplay(agent_id, _, _) => metrics.record_display(agent_id),
+                ASKitEvent::AgentError(agent_id, _) => metrics.record_error(agent_id),
+                ASKitEvent::AgentIn(agent_id, channel) => {
+                    metrics.record_input(&self.app, agent_id, channel)
+                }
+                _ => {}
+            }
+        }
+
+        if let ASKitEvent::AgentDisplay(agent_id, key, data) = event {
+            if let Some(recorder) = self.app.try_state::<Recorder>() {
+                recorder.record_display(&self.app, agent_id, key, data);
+            }
+            if let Some(index) = self.app.try_state::<TextIndex>() {
+                index.record_display(&self.app, agent_id, key, data);
+            }
+        }
+
+        if let Some(tracer) = self.app.try_state::<Tracer>() {
+            match event {
+                ASKitEvent::AgentDisplay(agent_id, key, data) => {
+                    tracer.record_display(&self.app, agent_id, key, data)
+                }
+                ASKitEvent::AgentError(agent_id, message) => {
+                    tracer.record_error(&self.app, agent_id, message)
+                }
+                ASKitEvent::AgentIn(agent_id, channel) => {
+                    tracer.record_input(&self.app, agent_id, channel)
+                }
+                _ => {}
+            }
+        }
+
         match event {
             ASKitEvent::AgentDisplay(agent_id, key, data) => {
                 self.emit_display(agent_id.to_string(), key.to_string(), data.clone())
@@ -74,6 +136,12 @@ impl ASKitObserver for ASAppObserver {
                     });
             }
             ASKitEvent::AgentError(agent_id, message) => {
+                log::error!(target: AGENT_LOG_TARGET, "{}: {}", agent_id, message);
+                if let Some(asapp) = self.app.try_state::<ASApp>() {
+                    asapp.push_error(agent_id, message);
+                    tray::update(&self.app);
+                }
+                notification::notify_error(&self.app, agent_id, message);
                 self.emit_error(agent_id.to_string(), message.to_string())
                     .unwrap_or_else(|e| {
                         log::error!("Failed to emit error message: {}", e);
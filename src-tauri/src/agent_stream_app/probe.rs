@@ -0,0 +1,97 @@
+use agent_stream_kit::{AgentFlowEdge, AgentFlowNode};
+use anyhow::{Context as _, Result};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+use tauri_plugin_askit::ASKitExt;
+
+/// Agent that receives the messages of the edges moved to a probe.
+/// Its display events carry the data, which the observer hands to the app.
+const PROBE_AGENT_DEF: &str = "std_display_data";
+const PROBE_INPUT: &str = "data";
+
+pub fn flow_edges(app: &AppHandle, flow_name: &str) -> Result<Vec<Value>> {
+    let flows = app.askit().get_agent_flows();
+    let flow = flows
+        .get(flow_name)
+        .with_context(|| format!("Agent flow not found: {}", flow_name))?;
+    let value = serde_json::to_value(flow)?;
+    Ok(value
+        .get("edges")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default())
+}
+
+pub fn edge_field<'a>(edge: &'a Value, key: &str) -> &'a str {
+    edge.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn same_connection(a: &Value, b: &Value) -> bool {
+    ["source", "source_handle", "target", "target_handle"]
+        .iter()
+        .all(|key| edge_field(a, key) == edge_field(b, key))
+}
+
+fn probe_edge_id(edge: &Value, suffix: &str) -> String {
+    format!("{}-{}", edge_field(edge, "id"), suffix)
+}
+
+pub fn add_probe(app: &AppHandle, flow_name: &str) -> Result<String> {
+    let askit = app.askit();
+    let mut probe = serde_json::to_value(askit.new_agent_flow_node(PROBE_AGENT_DEF)?)?;
+    probe["enabled"] = Value::Bool(true);
+    let probe: AgentFlowNode = serde_json::from_value(probe)?;
+    askit.add_agent_flow_node(flow_name, &probe)?;
+    Ok(probe.id)
+}
+
+pub async fn remove_probe(app: &AppHandle, flow_name: &str, probe_id: &str) -> Result<()> {
+    app.askit()
+        .remove_agent_flow_node(flow_name, probe_id)
+        .await?;
+    Ok(())
+}
+
+/// Move `edge` so that it goes into the probe. `suffix` tells apart the users of probes.
+pub fn redirect_edge(
+    app: &AppHandle,
+    flow_name: &str,
+    edge: &Value,
+    probe_id: &str,
+    suffix: &str,
+) -> Result<()> {
+    let askit = app.askit();
+    askit.remove_agent_flow_edge(flow_name, edge_field(edge, "id"))?;
+    let probe_edge: AgentFlowEdge = serde_json::from_value(json!({
+        "id": probe_edge_id(edge, suffix),
+        "source": edge_field(edge, "source"),
+        "source_handle": edge_field(edge, "source_handle"),
+        "target": probe_id,
+        "target_handle": PROBE_INPUT,
+    }))?;
+    askit.add_agent_flow_edge(flow_name, &probe_edge)?;
+    Ok(())
+}
+
+pub fn restore_edge(app: &AppHandle, flow_name: &str, edge: &Value, suffix: &str) -> Result<()> {
+    let askit = app.askit();
+    askit.remove_agent_flow_edge(flow_name, &probe_edge_id(edge, suffix))?;
+    let edge: AgentFlowEdge = serde_json::from_value(edge.clone())?;
+    askit.add_agent_flow_edge(flow_name, &edge)?;
+    Ok(())
+}
+
+pub fn strip_probe(flow: &mut Value, probe_id: &str, edges: &[Value]) {
+    if let Some(nodes) = flow.get_mut("nodes").and_then(Value::as_array_mut) {
+        nodes.retain(|node| node.get("id").and_then(Value::as_str) != Some(probe_id));
+    }
+    if let Some(flow_edges) = flow.get_mut("edges").and_then(Value::as_array_mut) {
+        flow_edges.retain(|edge| edge.get("target").and_then(Value::as_str) != Some(probe_id));
+        // The frontend may still have the original edges.
+        for edge in edges {
+            if !flow_edges.iter().any(|e| same_connection(e, edge)) {
+                flow_edges.push(edge.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{bail, Context as _, Result};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_store::StoreExt;
+
+use super::app::ASApp;
+use super::debugger::Debugger;
+use super::metrics::Metrics;
+use super::record::Recorder;
+use super::trace::Tracer;
+use super::{schedule, settings, text_index, tray, window};
+
+const PROFILES_JSON: &str = "profiles.json";
+const DEFAULT_PROFILE: &str = "default";
+const ASKIT_PATH: &str = ".askit";
+const EMIT_PROFILE_CHANGED: &str = "asapp:profile_changed";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub flows_dir: PathBuf,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profiles {
+    pub current: String,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Profiles {
+    fn new() -> Result<Self> {
+        let mut profiles = BTreeMap::new();
+        profiles.insert(
+            DEFAULT_PROFILE.to_string(),
+            Profile {
+                name: DEFAULT_PROFILE.to_string(),
+                flows_dir: default_flows_dir(DEFAULT_PROFILE)?,
+            },
+        );
+        Ok(Profiles {
+            current: DEFAULT_PROFILE.to_string(),
+            profiles,
+        })
+    }
+}
+
+pub fn init(app: &AppHandle) -> Result<()> {
+    let store = app.store(PROFILES_JSON)?;
+
+    let mut profiles = Profiles::new()?;
+    if let Some(value) = store.get("profiles") {
+        match serde_json::from_value::<Profiles>(value) {
+            Ok(stored) => {
+                profiles.profiles.extend(stored.profiles);
+                if profiles.profiles.contains_key(&stored.current) {
+                    profiles.current = stored.current;
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to load profiles: {}", e);
+            }
+        }
+    }
+    log::info!("profile: {}", profiles.current);
+
+    app.manage(Mutex::new(profiles));
+
+    Ok(())
+}
+
+fn save(app: &AppHandle) -> Result<()> {
+    let store = app.store(PROFILES_JSON)?;
+    let profiles = app.state::<Mutex<Profiles>>();
+    let value = serde_json::to_value(&*profiles.lock().unwrap())?;
+    store.set("profiles", value);
+    Ok(())
+}
+
+pub fn current_profile(app: &AppHandle) -> Profile {
+    let profiles = app.state::<Mutex<Profiles>>();
+    let profiles = profiles.lock().unwrap();
+    profiles.profiles[&profiles.current].clone()
+}
+
+pub fn current_settings_json(app: &AppHandle) -> String {
+    settings_json(&current_profile(app).name)
+}
+
+pub fn current_flows_dir(app: &AppHandle) -> Result<PathBuf> {
+    let flows_dir = current_profile(app).flows_dir;
+    if flows_dir.as_os_str().is_empty() {
+        bail!("Flows directory is not set");
+    }
+    Ok(flows_dir)
+}
+
+fn settings_json(profile_name: &str) -> String {
+    if profile_name == DEFAULT_PROFILE {
+        "settings.json".to_string()
+    } else {
+        format!("settings-{}.json", profile_name)
+    }
+}
+
+fn askit_dir() -> Result<PathBuf> {
+    let home_dir = dirs::home_dir().with_context(|| "Failed to get home directory")?;
+    Ok(home_dir.join(ASKIT_PATH))
+}
+
+fn default_flows_dir(profile_name: &str) -> Result<PathBuf> {
+    let askit_dir = askit_dir()?;
+    if profile_name == DEFAULT_PROFILE {
+        Ok(askit_dir.join("flows"))
+    } else {
+        Ok(askit_dir.join("profiles").join(profile_name).join("flows"))
+    }
+}
+
+fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!("Invalid profile name: {:?}", name);
+    }
+    Ok(())
+}
+
+pub fn create_profile(app: &AppHandle, name: &str, flows_dir: Option<PathBuf>) -> Result<Profile> {
+    validate_profile_name(name)?;
+
+    let flows_dir = match flows_dir {
+        Some(flows_dir) => flows_dir,
+        None => default_flows_dir(name)?,
+    };
+    let profile = Profile {
+        name: name.to_string(),
+        flows_dir,
+    };
+    {
+        let profiles = app.state::<Mutex<Profiles>>();
+        let mut profiles = profiles.lock().unwrap();
+        if profiles.profiles.contains_key(name) {
+            bail!("Profile already exists: {}", name);
+        }
+        profiles.profiles.insert(name.to_string(), profile.clone());
+    }
+    let result = std::fs::create_dir_all(&profile.flows_dir)
+        .with_context(|| format!("Failed to create flows directory: {:?}", profile.flows_dir))
+        .and_then(|_| save(app));
+    if let Err(e) = result {
+        let profiles = app.state::<Mutex<Profiles>>();
+        profiles.lock().unwrap().profiles.remove(name);
+        return Err(e);
+    }
+
+    Ok(profile)
+}
+
+pub fn clone_profile(app: &AppHandle, source: &str, name: &str) -> Result<Profile> {
+    let source_profile = {
+        let profiles = app.state::<Mutex<Profiles>>();
+        let profiles = profiles.lock().unwrap();
+        profiles
+            .profiles
+            .get(source)
+            .cloned()
+            .with_context(|| format!("Profile not found: {}", source))?
+    };
+    if source == current_profile(app).name {
+        settings::save(app)?;
+    }
+
+    let profile = create_profile(app, name, None)?;
+
+    if let Err(e) = copy_profile(app, &source_profile, &profile) {
+        // Don't leave a half-copied profile behind
+        delete_profile(app, name).unwrap_or_else(|e| {
+            log::error!("Failed to remove profile {}: {}", name, e);
+        });
+        return Err(e);
+    }
+
+    Ok(profile)
+}
+
+fn copy_profile(app: &AppHandle, source: &Profile, profile: &Profile) -> Result<()> {
+    let source_store = app.store(settings_json(&source.name))?;
+    let store = app.store(settings_json(&profile.name))?;
+    for (key, value) in source_store.entries() {
+        store.set(key, value);
+    }
+    store.save()?;
+
+    copy_dir_recursive(&source.flows_dir, &profile.flows_dir)
+}
+
+fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+    if !src.is_dir() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = dst.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir_recursive(&path, &target)?;
+        } else {
+            std::fs::copy(&path, &target).with_context(|| format!("Failed to copy {:?}", path))?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn switch_profile(app: &AppHandle, name: &str) -> Result<()> {
+    let current = current_profile(app);
+    if current.name == name {
+        return Ok(());
+    }
+
+    let flows_dir = {
+        let profiles = app.state::<Mutex<Profiles>>();
+        let profiles = profiles.lock().unwrap();
+        profiles
+            .profiles
+            .get(name)
+            .map(|p| p.flows_dir.clone())
+            .with_context(|| format!("Profile not found: {}", name))?
+    };
+
+    settings::save(app)?;
+    app.store(settings_json(&current.name))?.save()?;
+
+    let asapp = app.state::<ASApp>();
+    asapp.unload_agent_flows().await?;
+    app.state::<Debugger>().clear();
+    app.state::<Recorder>().clear();
+    app.state::<Tracer>().reset();
+    app.state::<Metrics>().reset();
+
+    let settings_result = match load_profile(app, name, flows_dir) {
+        Ok(settings_result) => settings_result,
+        Err(e) => {
+            log::error!("Failed to switch profile to {}: {}", name, e);
+            asapp.unload_agent_flows().await.unwrap_or_else(|e| {
+                log::error!("Failed to unload agent flows: {}", e);
+            });
+            if let Err(e) = load_profile(app, &current.name, current.flows_dir) {
+                log::error!("Failed to restore profile {}: {}", current.name, e);
+            }
+            tray::update(app);
+            return Err(e);
+        }
+    };
+
+    schedule::reload(app).unwrap_or_else(|e| {
+        log::error!("Failed to load schedules: {}", e);
+    });
+    text_index::reload(app).unwrap_or_else(|e| {
+        log::error!("Failed to index agent flows: {}", e);
+    });
+
+    window::reload(app);
+
+    tray::update(app);
+    app.emit(EMIT_PROFILE_CHANGED, name)?;
+
+    log::info!("switched profile: {}", name);
+
+    settings_result.map_err(|e| anyhow::anyhow!("{}: {:?}", e.message, e.fields))
+}
+
+/// Settings that fail to apply don't stop the flows from loading,
+/// and are returned in the inner result.
+fn load_profile(
+    app: &AppHandle,
+    name: &str,
+    flows_dir: PathBuf,
+) -> Result<Result<(), settings::SettingsError>> {
+    {
+        let profiles = app.state::<Mutex<Profiles>>();
+        profiles.lock().unwrap().current = name.to_string();
+    }
+    save(app)?;
+
+    let settings_result = match settings::reload(app) {
+        Err(e) if e.fields.is_empty() => bail!("{}", e.message),
+        result => result,
+    };
+
+    app.state::<ASApp>().load_agent_flows(flows_dir)?;
+
+    Ok(settings_result)
+}
+
+pub fn delete_profile(app: &AppHandle, name: &str) -> Result<()> {
+    if name == DEFAULT_PROFILE {
+        bail!("The default profile cannot be deleted");
+    }
+    if name == current_profile(app).name {
+        bail!("The current profile cannot be deleted");
+    }
+
+    let profile = {
+        let profiles = app.state::<Mutex<Profiles>>();
+        let mut profiles = profiles.lock().unwrap();
+        profiles
+            .profiles
+            .remove(name)
+            .with_context(|| format!("Profile not found: {}", name))?
+    };
+    save(app)?;
+
+    let store = app.store(settings_json(name))?;
+    store.clear();
+    store.save()?;
+    let store_path = app.path().app_data_dir()?.join(settings_json(name));
+    if store_path.exists() {
+        std::fs::remove_file(store_path).context("Failed to remove profile settings")?;
+    }
+
+    // Only remove flows that live in the directory managed for this profile.
+    if default_flows_dir(name).is_ok_and(|dir| dir == profile.flows_dir)
+        && profile.flows_dir.exists()
+    {
+        std::fs::remove_dir_all(&profile.flows_dir)
+            .context("Failed to remove profile flows directory")?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_profiles_cmd(profiles: State<Mutex<Profiles>>) -> Profiles {
+    profiles.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn create_profile_cmd(
+    app: AppHandle,
+    name: String,
+    flows_dir: Option<PathBuf>,
+) -> Result<Profile, String> {
+    create_profile(&app, &name, flows_dir).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn clone_profile_cmd(app: AppHandle, source: String, name: String) -> Result<Profile, String> {
+    clone_profile(&app, &source, &name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn switch_profile_cmd(app: AppHandle, name: String) -> Result<(), String> {
+    switch_profile(&app, &name).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_profile_cmd(app: AppHandle, name: String) -> Result<(), String> {
+    delete_profile(&app, &name).map_err(|e| e.to_string())
+}
@@ -0,0 +1,207 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, DragDropEvent, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use super::app::{self, ASApp};
+use super::settings::{self, CoreSettings, QuickInputSettings};
+
+pub const QUICK_INPUT_LABEL: &str = "quick_input";
+const QUICK_INPUT_ROUTE: &str = "/quick_input";
+const QUICK_INPUT_SIZE: (f64, f64) = (640.0, 360.0);
+const MAX_FILE_CONTENTS_SIZE: u64 = 1024 * 1024;
+/// Time to start dragging a file from another app, which takes the focus, into the overlay.
+const BLUR_GRACE: Duration = Duration::from_millis(1500);
+
+static DRAGGING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct QuickInputTarget {
+    pub flow: String,
+    pub agent_id: String,
+    pub ch: String,
+    pub display_agent_id: Option<String>,
+}
+
+fn quick_input_settings(app: &AppHandle) -> Result<QuickInputSettings> {
+    let settings = app.state::<Mutex<CoreSettings>>();
+    let settings = settings.lock().unwrap();
+    settings
+        .quick_input
+        .clone()
+        .context("Quick input is not configured")
+}
+
+fn target(app: &AppHandle) -> Result<QuickInputTarget> {
+    let settings = quick_input_settings(app)?;
+    let asapp = app.state::<ASApp>();
+    let agent_id = asapp.find_agent_node(&settings.flow, &settings.node)?;
+    let display_agent_id = match &settings.display_node {
+        Some(node) => Some(asapp.find_agent_node(&settings.flow, node)?),
+        None => None,
+    };
+    Ok(QuickInputTarget {
+        flow: settings.flow,
+        agent_id,
+        ch: settings.ch,
+        display_agent_id,
+    })
+}
+
+pub fn open(app: &AppHandle) -> Result<()> {
+    if let Some(window) = app.get_webview_window(QUICK_INPUT_LABEL) {
+        window.show()?;
+        window.set_focus()?;
+        return Ok(());
+    }
+    let (width, height) = QUICK_INPUT_SIZE;
+    WebviewWindowBuilder::new(
+        app,
+        QUICK_INPUT_LABEL,
+        WebviewUrl::App(QUICK_INPUT_ROUTE.into()),
+    )
+    .title("Quick Input")
+    .inner_size(width, height)
+    .center()
+    .decorations(false)
+    .resizable(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .focused(true)
+    .build()?;
+    Ok(())
+}
+
+/// The overlay is created again on each open, so it always shows the current flows.
+pub fn close(app: &AppHandle) {
+    DRAGGING.store(false, Ordering::Relaxed);
+    if let Some(window) = app.get_webview_window(QUICK_INPUT_LABEL) {
+        window.destroy().unwrap_or_else(|e| {
+            log::error!("Failed to close quick input: {}", e);
+        });
+    }
+}
+
+pub fn blurred(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(BLUR_GRACE).await;
+        close_if_inactive(&app);
+    });
+}
+
+pub fn drag_drop(app: &AppHandle, event: &DragDropEvent) {
+    match event {
+        DragDropEvent::Enter { .. } | DragDropEvent::Over { .. } => {
+            DRAGGING.store(true, Ordering::Relaxed);
+        }
+        DragDropEvent::Leave => {
+            DRAGGING.store(false, Ordering::Relaxed);
+            close_if_inactive(app);
+        }
+        DragDropEvent::Drop { .. } => {
+            DRAGGING.store(false, Ordering::Relaxed);
+            // Take the focus back, so that the overlay closes on the next blur.
+            if let Some(window) = app.get_webview_window(QUICK_INPUT_LABEL) {
+                window.set_focus().unwrap_or_else(|e| {
+                    log::error!("Failed to focus quick input: {}", e);
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+fn close_if_inactive(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(QUICK_INPUT_LABEL) else {
+        return;
+    };
+    if DRAGGING.load(Ordering::Relaxed) || window.is_focused().unwrap_or(false) {
+        return;
+    }
+    close(app);
+}
+
+pub fn set_target(app: &AppHandle, flow: String, node: String) -> Result<QuickInputTarget> {
+    {
+        let core_settings = app.state::<Mutex<CoreSettings>>();
+        let mut core_settings = core_settings.lock().unwrap();
+        let quick_input = core_settings
+            .quick_input
+            .get_or_insert_with(|| QuickInputSettings {
+                flow: flow.clone(),
+                node: node.clone(),
+                ch: settings::default_quick_input_ch(),
+                display_node: None,
+                on_global_shortcut: false,
+            });
+        if quick_input.flow != flow {
+            quick_input.display_node = None;
+        }
+        quick_input.flow = flow;
+        quick_input.node = node;
+    }
+    settings::save(app)?;
+    target(app)
+}
+
+fn file_value(path: &Path) -> Result<Value> {
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read {:?}", path))?
+        .len();
+    let contents = if size <= MAX_FILE_CONTENTS_SIZE {
+        // Binary files are sent without contents
+        std::fs::read_to_string(path).ok()
+    } else {
+        None
+    };
+    Ok(json!({
+        "path": path.to_string_lossy(),
+        "name": path.file_name().map(|name| name.to_string_lossy()),
+        "size": size,
+        "contents": contents,
+    }))
+}
+
+pub async fn submit(app: &AppHandle, text: Option<String>, path: Option<PathBuf>) -> Result<()> {
+    let target = target(app)?;
+    let value = match (text, path) {
+        (_, Some(path)) => file_value(&path)?,
+        (Some(text), None) => Value::String(text),
+        (None, None) => return Ok(()),
+    };
+
+    let asapp = app.state::<ASApp>();
+    if !asapp.is_agent_flow_running(&target.flow) {
+        asapp.start_agent_flow(&target.flow).await?;
+    }
+    app::send_input(app, &target.agent_id, &target.ch, value).await
+}
+
+#[tauri::command]
+pub fn get_quick_input_target_cmd(app: AppHandle) -> Result<QuickInputTarget, String> {
+    target(&app).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_quick_input_target_cmd(
+    app: AppHandle,
+    flow: String,
+    node: String,
+) -> Result<QuickInputTarget, String> {
+    set_target(&app, flow, node).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn submit_quick_input_cmd(
+    app: AppHandle,
+    text: Option<String>,
+    path: Option<PathBuf>,
+) -> Result<(), String> {
+    submit(&app, text, path).await.map_err(|e| e.to_string())
+}
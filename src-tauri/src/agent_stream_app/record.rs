@@ -0,0 +1,550 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use agent_stream_kit::{AgentData, AgentFlow};
+use anyhow::{bail, Context as _, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_askit::ASKitExt;
+use tokio::sync::mpsc;
+
+use super::app::ASApp;
+use super::probe::{self, edge_field};
+use super::topology::Topology;
+
+const DEFAULT_SETTLE_MS: u64 = 1000;
+const PROBE_EDGE_SUFFIX: &str = "record";
+
+/// Nodes are referred to by their ids in the saved flow file,
+/// since node ids change when a flow is loaded.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Recording {
+    pub flow: String,
+    pub inputs: Vec<RecordedInput>,
+    pub displays: Vec<RecordedDisplay>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedInput {
+    pub offset_ms: u64,
+    pub node: String,
+    pub ch: String,
+    pub value: Value,
+    /// The value is the data output by a source node, rather than a value sent by the app.
+    #[serde(default)]
+    pub source: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedDisplay {
+    pub node: String,
+    pub key: String,
+    pub value: Value,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReplayOptions {
+    /// Multiplier of the recorded delays between inputs. 0 sends them without waiting.
+    pub time_scale: Option<f64>,
+    pub settle_ms: Option<u64>,
+    pub golden: Option<PathBuf>,
+    #[serde(default)]
+    pub update_golden: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReplayReport {
+    pub flow: String,
+    pub inputs: usize,
+    pub expected: usize,
+    pub actual: usize,
+    pub mismatches: Vec<DisplayMismatch>,
+    pub passed: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DisplayMismatch {
+    pub node: String,
+    pub key: String,
+    pub index: usize,
+    pub expected: Option<Value>,
+    pub actual: Option<Value>,
+}
+
+#[derive(Default)]
+pub struct Recorder(Mutex<RecorderState>);
+
+#[derive(Default)]
+struct RecorderState {
+    sessions: HashMap<String, Session>,
+    topology: Topology,
+}
+
+struct Session {
+    started: Instant,
+    recording: Recording,
+    replay: bool,
+    taps: HashMap<String, Value>,
+    /// Passes the tapped data on to the targets in the order it arrived.
+    forward: Option<mpsc::UnboundedSender<(String, String, AgentData)>>,
+}
+
+impl Recorder {
+    fn start(
+        &self,
+        app: &AppHandle,
+        flow: &str,
+        replay: bool,
+        taps: HashMap<String, Value>,
+    ) -> Result<()> {
+        let mut state = self.0.lock().unwrap();
+        if state.sessions.contains_key(flow) {
+            bail!("Agent flow is already being recorded or replayed: {}", flow);
+        }
+        let forward = (!taps.is_empty()).then(|| forward_taps(app));
+        state.sessions.insert(
+            flow.to_string(),
+            Session {
+                started: Instant::now(),
+                recording: Recording {
+                    flow: flow.to_string(),
+                    ..Default::default()
+                },
+                replay,
+                taps,
+                forward,
+            },
+        );
+        Ok(())
+    }
+
+    fn has_session(&self, flow: &str) -> bool {
+        self.0.lock().unwrap().sessions.contains_key(flow)
+    }
+
+    fn stop(&self, flow: &str) -> Option<Session> {
+        self.0.lock().unwrap().sessions.remove(flow)
+    }
+
+    pub fn clear(&self) {
+        *self.0.lock().unwrap() = RecorderState::default();
+    }
+
+    pub fn is_recording(&self, flow: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .sessions
+            .get(flow)
+            .is_some_and(|session| !session.replay)
+    }
+
+    pub fn is_tap(&self, agent_id: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .sessions
+            .values()
+            .any(|session| session.taps.contains_key(agent_id))
+    }
+
+    pub fn tap(&self, app: &AppHandle, probe_id: &str, data: AgentData) {
+        let mut state = self.0.lock().unwrap();
+        let Some(session) = state
+            .sessions
+            .values_mut()
+            .find(|session| session.taps.contains_key(probe_id))
+        else {
+            return;
+        };
+        let edge = &session.taps[probe_id];
+        let target = edge_field(edge, "target").to_string();
+        let ch = edge_field(edge, "target_handle").to_string();
+        session.recording.inputs.push(RecordedInput {
+            offset_ms: session.started.elapsed().as_millis() as u64,
+            node: app.state::<ASApp>().file_node_id(&target),
+            ch: ch.clone(),
+            value: serde_json::to_value(&data).unwrap_or_default(),
+            source: true,
+        });
+        if let Some(forward) = &session.forward {
+            forward.send((target, ch, data)).unwrap_or_else(|e| {
+                log::error!("Failed to pass recorded data: {}", e);
+            });
+        }
+    }
+
+    pub fn record_input(&self, app: &AppHandle, agent_id: &str, ch: &str, value: &Value) {
+        let mut state = self.0.lock().unwrap();
+        if state.sessions.is_empty() {
+            return;
+        }
+        state.topology.refresh(app);
+        let Some(flow) = state.topology.flow_of(agent_id).cloned() else {
+            return;
+        };
+        let Some(session) = state.sessions.get_mut(&flow).filter(|s| !s.replay) else {
+            return;
+        };
+        // The outputs of tapped source nodes are recorded instead.
+        if session
+            .taps
+            .values()
+            .any(|edge| edge_field(edge, "source") == agent_id)
+        {
+            return;
+        }
+        session.recording.inputs.push(RecordedInput {
+            offset_ms: session.started.elapsed().as_millis() as u64,
+            node: app.state::<ASApp>().file_node_id(agent_id),
+            ch: ch.to_string(),
+            value: value.clone(),
+            source: false,
+        });
+    }
+
+    pub fn record_display(
+        &self,
+        app: &AppHandle,
+        agent_id: &str,
+        key: &str,
+        data: &impl Serialize,
+    ) {
+        let mut state = self.0.lock().unwrap();
+        if state.sessions.is_empty() {
+            return;
+        }
+        state.topology.refresh(app);
+        let Some(flow) = state.topology.flow_of(agent_id).cloned() else {
+            return;
+        };
+        let Some(session) = state.sessions.get_mut(&flow) else {
+            return;
+        };
+        session.recording.displays.push(RecordedDisplay {
+            node: app.state::<ASApp>().file_node_id(agent_id),
+            key: key.to_string(),
+            value: serde_json::to_value(data).unwrap_or_default(),
+        });
+    }
+
+    pub fn restore_flow(&self, flow: AgentFlow) -> Result<AgentFlow> {
+        let state = self.0.lock().unwrap();
+        let Some(session) = state.sessions.get(flow.name()) else {
+            return Ok(flow);
+        };
+        let mut value = serde_json::to_value(&flow)?;
+        for (probe_id, edge) in &session.taps {
+            probe::strip_probe(&mut value, probe_id, std::slice::from_ref(edge));
+        }
+        serde_json::from_value(value).context("Failed to restore agent flow edges")
+    }
+}
+
+/// The task ends when the session is stopped and the sender is dropped.
+fn forward_taps(app: &AppHandle) -> mpsc::UnboundedSender<(String, String, AgentData)> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<(String, String, AgentData)>();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some((target, ch, data)) = rx.recv().await {
+            app.askit()
+                .agent_input(target.clone(), ch, data)
+                .await
+                .unwrap_or_else(|e| {
+                    log::error!("Failed to pass recorded data to {}: {}", target, e);
+                });
+        }
+    });
+    tx
+}
+
+fn source_nodes(app: &AppHandle, flow_name: &str) -> Result<Vec<(String, bool)>> {
+    let flows = app.askit().get_agent_flows();
+    let flow = flows
+        .get(flow_name)
+        .with_context(|| format!("Agent flow not found: {}", flow_name))?;
+    let edges = probe::flow_edges(app, flow_name)?;
+    Ok(flow
+        .nodes()
+        .iter()
+        .filter(|n| !edges.iter().any(|e| edge_field(e, "target") == n.id))
+        .map(|n| (n.id.clone(), n.enabled))
+        .collect())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid file: {:?}", path))
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    let json = serde_json::to_string_pretty(value)?;
+    std::fs::write(path, json).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Start recording the flow, tapping the outputs of its source nodes
+/// so that timers, watchers and the like are recorded too.
+pub async fn start_recording(app: &AppHandle, flow: &str) -> Result<()> {
+    if app.state::<Recorder>().has_session(flow) {
+        bail!("Agent flow is already being recorded or replayed: {}", flow);
+    }
+    let sources = source_nodes(app, flow)?;
+    let source_edges: Vec<Value> = probe::flow_edges(app, flow)?
+        .into_iter()
+        .filter(|edge| {
+            sources
+                .iter()
+                .any(|(id, _)| edge_field(edge, "source") == id)
+        })
+        .collect();
+
+    let mut taps = HashMap::new();
+    let mut result = Ok(());
+    for edge in source_edges {
+        let tapped = probe::add_probe(app, flow).and_then(|probe_id| {
+            probe::redirect_edge(app, flow, &edge, &probe_id, PROBE_EDGE_SUFFIX)?;
+            Ok(probe_id)
+        });
+        match tapped {
+            Ok(probe_id) => {
+                taps.insert(probe_id, edge);
+            }
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+    if let Err(e) = result {
+        remove_taps(app, flow, taps).await;
+        return Err(e);
+    }
+
+    if let Err(e) = app
+        .state::<Recorder>()
+        .start(app, flow, false, taps.clone())
+    {
+        remove_taps(app, flow, taps).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+async fn remove_taps(app: &AppHandle, flow: &str, taps: HashMap<String, Value>) {
+    for (probe_id, edge) in taps {
+        probe::restore_edge(app, flow, &edge, PROBE_EDGE_SUFFIX).unwrap_or_else(|e| {
+            log::error!("Failed to restore edge {}: {}", edge_field(&edge, "id"), e);
+        });
+        probe::remove_probe(app, flow, &probe_id)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to remove probe {}: {}", probe_id, e);
+            });
+    }
+}
+
+pub async fn stop_recording(app: &AppHandle, flow: &str, path: &Path) -> Result<Recording> {
+    let session = app
+        .state::<Recorder>()
+        .stop(flow)
+        .filter(|session| !session.replay)
+        .with_context(|| format!("Agent flow is not being recorded: {}", flow))?;
+    remove_taps(app, flow, session.taps).await;
+    let recording = session.recording;
+    write_json(path, &recording)?;
+    log::info!(
+        "saved recording of {} with {} inputs: {:?}",
+        flow,
+        recording.inputs.len(),
+        path
+    );
+    Ok(recording)
+}
+
+/// The source nodes are stopped during the replay, and their recorded outputs are sent instead.
+/// A flow that wasn't running is stopped again afterwards.
+pub async fn replay(app: &AppHandle, path: &Path, options: &ReplayOptions) -> Result<ReplayReport> {
+    let recording: Recording = read_json(path)?;
+    let flow = recording.flow.as_str();
+    let recorder = app.state::<Recorder>();
+    if recorder.has_session(flow) {
+        bail!("Agent flow is already being recorded or replayed: {}", flow);
+    }
+
+    let asapp = app.state::<ASApp>();
+    let askit = app.askit();
+    let sources = if recording.inputs.iter().any(|input| input.source) {
+        source_nodes(app, flow)?
+    } else {
+        Vec::new()
+    };
+
+    let time_scale = options.time_scale.unwrap_or(1.0).max(0.0);
+    let mut last_offset_ms = 0;
+    let mut started_flow = false;
+    let mut stopped_sources = Vec::new();
+    let mut in_session = false;
+    let result = async {
+        if asapp.is_agent_flow_running(flow) {
+            for (id, enabled) in sources {
+                if enabled {
+                    askit.stop_agent(&id).await?;
+                    stopped_sources.push(id);
+                }
+            }
+        } else {
+            let source_ids: Vec<String> = sources.into_iter().map(|(id, _)| id).collect();
+            started_flow = true;
+            asapp.start_agent_flow_except(flow, &source_ids).await?;
+        }
+
+        recorder.start(app, flow, true, HashMap::new())?;
+        in_session = true;
+
+        for input in &recording.inputs {
+            let delay_ms = input.offset_ms.saturating_sub(last_offset_ms) as f64 * time_scale;
+            last_offset_ms = input.offset_ms;
+            if delay_ms > 0.0 {
+                tokio::time::sleep(Duration::from_secs_f64(delay_ms / 1000.0)).await;
+            }
+            let agent_id = asapp
+                .find_agent_node_by_file_id(flow, &input.node)
+                .with_context(|| format!("Agent node not found: {} in {}", input.node, flow))?;
+            let data = if input.source {
+                serde_json::from_value(input.value.clone())?
+            } else {
+                AgentData::from_json_value(input.value.clone())?
+            };
+            askit.agent_input(agent_id, input.ch.clone(), data).await?;
+        }
+        let settle_ms = options.settle_ms.unwrap_or(DEFAULT_SETTLE_MS);
+        tokio::time::sleep(Duration::from_millis(settle_ms)).await;
+        anyhow::Ok(())
+    }
+    .await;
+    let actual = if in_session {
+        recorder
+            .stop(flow)
+            .map(|session| session.recording.displays)
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    if started_flow {
+        asapp.stop_agent_flow(flow).await.unwrap_or_else(|e| {
+            log::error!("Failed to stop agent flow {}: {}", flow, e);
+        });
+    }
+    for id in &stopped_sources {
+        askit.start_agent(id).await.unwrap_or_else(|e| {
+            log::error!("Failed to restart source node {}: {}", id, e);
+        });
+    }
+    result?;
+
+    let expected = match &options.golden {
+        Some(golden) if options.update_golden => {
+            write_json(golden, &actual)?;
+            log::info!("updated golden file: {:?}", golden);
+            actual.clone()
+        }
+        Some(golden) => read_json(golden)?,
+        None => recording.displays.clone(),
+    };
+
+    let mismatches = compare_displays(&expected, &actual);
+    Ok(ReplayReport {
+        flow: recording.flow.clone(),
+        inputs: recording.inputs.len(),
+        expected: expected.len(),
+        actual: actual.len(),
+        passed: mismatches.is_empty(),
+        mismatches,
+    })
+}
+
+/// Agents run concurrently, so only the order within a node and key is deterministic.
+fn compare_displays(
+    expected: &[RecordedDisplay],
+    actual: &[RecordedDisplay],
+) -> Vec<DisplayMismatch> {
+    fn group(displays: &[RecordedDisplay]) -> BTreeMap<(&str, &str), Vec<&Value>> {
+        let mut groups: BTreeMap<(&str, &str), Vec<&Value>> = BTreeMap::new();
+        for display in displays {
+            groups
+                .entry((display.node.as_str(), display.key.as_str()))
+                .or_default()
+                .push(&display.value);
+        }
+        groups
+    }
+
+    let expected = group(expected);
+    let actual = group(actual);
+    let mut keys: Vec<&(&str, &str)> = expected.keys().chain(actual.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut mismatches = Vec::new();
+    for key in keys {
+        let expected_values = expected.get(key).map(Vec::as_slice).unwrap_or_default();
+        let actual_values = actual.get(key).map(Vec::as_slice).unwrap_or_default();
+        for index in 0..expected_values.len().max(actual_values.len()) {
+            let e = expected_values.get(index).copied();
+            let a = actual_values.get(index).copied();
+            if e != a {
+                mismatches.push(DisplayMismatch {
+                    node: key.0.to_string(),
+                    key: key.1.to_string(),
+                    index,
+                    expected: e.cloned(),
+                    actual: a.cloned(),
+                });
+            }
+        }
+    }
+    mismatches
+}
+
+pub fn init(app: &AppHandle) -> Result<()> {
+    app.manage(Recorder::default());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn start_recording_cmd(app: AppHandle, flow: String) -> Result<(), String> {
+    start_recording(&app, &flow)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn stop_recording_cmd(
+    app: AppHandle,
+    flow: String,
+    path: PathBuf,
+) -> Result<Recording, String> {
+    stop_recording(&app, &flow, &path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn is_recording_cmd(recorder: State<Recorder>, flow: String) -> bool {
+    recorder.is_recording(&flow)
+}
+
+#[tauri::command]
+pub async fn replay_recording_cmd(
+    app: AppHandle,
+    path: PathBuf,
+    options: ReplayOptions,
+) -> Result<ReplayReport, String> {
+    replay(&app, &path, &options)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{bail, Context as _, Result};
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+
+use super::app::{self, ASApp};
+use super::{settings, tray};
+
+const SCHEDULES_KEY: &str = "schedules";
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_MISSED_RUNS: usize = 100;
+const MAX_INTERVAL_SECONDS: u64 = 366 * 24 * 60 * 60;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleEntry {
+    #[serde(default)]
+    pub id: String,
+    pub flow: String,
+    pub trigger: ScheduleTrigger,
+    pub action: ScheduleAction,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub missed: MissedRunPolicy,
+    #[serde(default)]
+    pub last_run: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTrigger {
+    /// Cron expression with seconds in local time, such as "0 0 9 * * Mon-Fri".
+    Cron {
+        expr: String,
+    },
+    Interval {
+        seconds: u64,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleAction {
+    Start,
+    Stop,
+    SendToInput {
+        node: String,
+        #[serde(default = "default_input_ch")]
+        ch: String,
+        #[serde(default)]
+        value: Value,
+    },
+}
+
+fn default_input_ch() -> String {
+    "in".to_string()
+}
+
+/// What to do with the runs that were due while the app was closed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    #[default]
+    Skip,
+    RunOnce,
+    RunAll,
+}
+
+impl ScheduleTrigger {
+    fn validate(&self) -> Result<()> {
+        match self {
+            ScheduleTrigger::Cron { expr } => {
+                cron::Schedule::from_str(expr)
+                    .with_context(|| format!("Invalid cron expression: {}", expr))?;
+            }
+            ScheduleTrigger::Interval { seconds } => {
+                if *seconds == 0 {
+                    bail!("Interval must be at least one second");
+                }
+                if *seconds > MAX_INTERVAL_SECONDS {
+                    bail!("Interval must be at most {} seconds", MAX_INTERVAL_SECONDS);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            ScheduleTrigger::Cron { expr } => cron::Schedule::from_str(expr)
+                .ok()
+                .and_then(|schedule| schedule.after(&time.with_timezone(&Local)).next())
+                .map(|next| next.with_timezone(&Utc)),
+            ScheduleTrigger::Interval { seconds } => i64::try_from(*seconds)
+                .ok()
+                .and_then(TimeDelta::try_seconds)
+                .and_then(|interval| time.checked_add_signed(interval)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScheduleInfo {
+    #[serde(flatten)]
+    pub entry: ScheduleEntry,
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+pub struct Scheduler {
+    entries: Mutex<Vec<ScheduleEntry>>,
+    next_runs: Mutex<HashMap<String, DateTime<Utc>>>,
+    task: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+}
+
+impl Scheduler {
+    pub fn list(&self) -> Vec<ScheduleInfo> {
+        let next_runs = self.next_runs.lock().unwrap();
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|entry| ScheduleInfo {
+                entry: entry.clone(),
+                next_run: next_runs.get(&entry.id).copied(),
+            })
+            .collect()
+    }
+
+    pub fn upcoming(&self, limit: usize) -> Vec<ScheduleInfo> {
+        let mut infos: Vec<ScheduleInfo> = self
+            .list()
+            .into_iter()
+            .filter(|info| info.entry.enabled && info.next_run.is_some())
+            .collect();
+        infos.sort_by_key(|info| info.next_run);
+        infos.truncate(limit);
+        infos
+    }
+
+    fn schedule_next(&self, entry: &ScheduleEntry, now: DateTime<Utc>) {
+        let mut next_runs = self.next_runs.lock().unwrap();
+        match entry.trigger.next_after(now).filter(|_| entry.enabled) {
+            Some(next_run) => next_runs.insert(entry.id.clone(), next_run),
+            None => next_runs.remove(&entry.id),
+        };
+    }
+
+    fn take_due(&self, now: DateTime<Utc>) -> Vec<ScheduleEntry> {
+        let mut due = Vec::new();
+        let mut entries = self.entries.lock().unwrap();
+        let mut next_runs = self.next_runs.lock().unwrap();
+        for entry in entries.iter_mut() {
+            if next_runs.get(&entry.id).is_some_and(|next| *next <= now) {
+                entry.last_run = Some(now);
+                match entry.trigger.next_after(now) {
+                    Some(next_run) => next_runs.insert(entry.id.clone(), next_run),
+                    None => next_runs.remove(&entry.id),
+                };
+                due.push(entry.clone());
+            }
+        }
+        due
+    }
+}
+
+fn missed_runs(entry: &ScheduleEntry, now: DateTime<Utc>) -> usize {
+    let Some(last_run) = entry.last_run.or(entry.created_at) else {
+        return 0;
+    };
+    let mut count = 0;
+    let mut time = last_run;
+    while let Some(next) = entry.trigger.next_after(time).filter(|next| *next <= now) {
+        count += 1;
+        if count >= MAX_MISSED_RUNS {
+            break;
+        }
+        time = next;
+    }
+    match entry.missed {
+        MissedRunPolicy::Skip => 0,
+        MissedRunPolicy::RunOnce => count.min(1),
+        MissedRunPolicy::RunAll => count,
+    }
+}
+
+fn read_schedules(app: &AppHandle) -> Result<Vec<ScheduleEntry>> {
+    let store = settings::settings_store(app)?;
+    let Some(value) = store.get(SCHEDULES_KEY) else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_value(value).context("Failed to read schedules")
+}
+
+fn save(app: &AppHandle) -> Result<()> {
+    let entries = app.state::<Scheduler>().entries.lock().unwrap().clone();
+    let store = settings::settings_store(app)?;
+    store.set(SCHEDULES_KEY, serde_json::to_value(entries)?);
+    Ok(())
+}
+
+pub fn init(app: &AppHandle) -> Result<()> {
+    app.manage(Scheduler::default());
+    reload(app)?;
+
+    let app_handle = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            tick(&app_handle).await;
+        }
+    });
+    *app.state::<Scheduler>().task.lock().unwrap() = Some(task);
+
+    Ok(())
+}
+
+pub fn reload(app: &AppHandle) -> Result<()> {
+    let mut entries = read_schedules(app)?;
+    let now = Utc::now();
+
+    let scheduler = app.state::<Scheduler>();
+    scheduler.next_runs.lock().unwrap().clear();
+    let mut missed = Vec::new();
+    for entry in entries.iter_mut() {
+        if !entry.enabled {
+            continue;
+        }
+        let count = missed_runs(entry, now);
+        if count > 0 {
+            log::info!("running {} missed runs of schedule {}", count, entry.id);
+            // Mark them as run so that they are not made up again on the next reload.
+            entry.last_run = Some(now);
+            missed.extend(std::iter::repeat(entry.clone()).take(count));
+        }
+        scheduler.schedule_next(entry, now);
+    }
+    *scheduler.entries.lock().unwrap() = entries;
+
+    if !missed.is_empty() {
+        save(app)?;
+    }
+
+    if !missed.is_empty() {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            for entry in missed {
+                run(&app, &entry).await;
+            }
+        });
+    }
+    tray::update(app);
+
+    Ok(())
+}
+
+async fn tick(app: &AppHandle) {
+    let due = app.state::<Scheduler>().take_due(Utc::now());
+    if due.is_empty() {
+        return;
+    }
+    for entry in &due {
+        run(app, entry).await;
+    }
+    save(app).unwrap_or_else(|e| {
+        log::error!("Failed to save schedules: {}", e);
+    });
+    tray::update(app);
+}
+
+async fn run(app: &AppHandle, entry: &ScheduleEntry) {
+    log::info!(
+        "run schedule {}: {:?} {}",
+        entry.id,
+        entry.action,
+        entry.flow
+    );
+    let asapp = app.state::<ASApp>();
+    let result = match &entry.action {
+        ScheduleAction::Start => {
+            if asapp.is_agent_flow_running(&entry.flow) {
+                Ok(())
+            } else {
+                asapp.start_agent_flow(&entry.flow).await
+            }
+        }
+        ScheduleAction::Stop => {
+            if asapp.is_agent_flow_running(&entry.flow) {
+                asapp.stop_agent_flow(&entry.flow).await
+            } else {
+                Ok(())
+            }
+        }
+        ScheduleAction::SendToInput { node, ch, value } => {
+            app::send_flow_input(app, &entry.flow, node, ch, value.clone()).await
+        }
+    };
+    if let Err(e) = result {
+        log::error!("Failed to run schedule {}: {}", entry.id, e);
+    }
+}
+
+pub fn set_schedule(app: &AppHandle, mut entry: ScheduleEntry) -> Result<ScheduleEntry> {
+    entry.trigger.validate()?;
+    if entry.flow.is_empty() {
+        bail!("Schedule has no flow");
+    }
+
+    let scheduler = app.state::<Scheduler>();
+    {
+        let mut entries = scheduler.entries.lock().unwrap();
+        if entry.id.is_empty() {
+            entry.id = new_schedule_id(&entries);
+        }
+        match entries.iter_mut().find(|e| e.id == entry.id) {
+            Some(existing) => {
+                // Keep the run history unless a new one is given
+                if entry.last_run.is_none() {
+                    entry.last_run = existing.last_run;
+                }
+                if entry.created_at.is_none() {
+                    entry.created_at = existing.created_at;
+                }
+                *existing = entry.clone();
+            }
+            None => {
+                if entry.created_at.is_none() {
+                    entry.created_at = Some(Utc::now());
+                }
+                entries.push(entry.clone());
+            }
+        }
+    }
+    scheduler.schedule_next(&entry, Utc::now());
+
+    save(app)?;
+    tray::update(app);
+    Ok(entry)
+}
+
+pub fn remove_schedule(app: &AppHandle, id: &str) -> Result<()> {
+    let scheduler = app.state::<Scheduler>();
+    {
+        let mut entries = scheduler.entries.lock().unwrap();
+        let len = entries.len();
+        entries.retain(|e| e.id != id);
+        if entries.len() == len {
+            bail!("Schedule not found: {}", id);
+        }
+    }
+    scheduler.next_runs.lock().unwrap().remove(id);
+
+    save(app)?;
+    tray::update(app);
+    Ok(())
+}
+
+fn new_schedule_id(entries: &[ScheduleEntry]) -> String {
+    let base = format!("{:x}", Utc::now().timestamp_millis());
+    let mut id = base.clone();
+    let mut n = 1;
+    while entries.iter().any(|e| e.id == id) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+#[tauri::command]
+pub fn list_schedules_cmd(scheduler: State<Scheduler>) -> Vec<ScheduleInfo> {
+    scheduler.list()
+}
+
+#[tauri::command]
+pub fn set_schedule_cmd(app: AppHandle, entry: ScheduleEntry) -> Result<ScheduleEntry, String> {
+    set_schedule(&app, entry).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_schedule_cmd(app: AppHandle, id: String) -> Result<(), String> {
+    remove_schedule(&app, &id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(trigger: ScheduleTrigger, missed: MissedRunPolicy) -> ScheduleEntry {
+        ScheduleEntry {
+            id: "test".to_string(),
+            flow: "flow".to_string(),
+            trigger,
+            action: ScheduleAction::Start,
+            enabled: true,
+            missed,
+            last_run: None,
+            created_at: None,
+        }
+    }
+
+    fn time(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_next_after_interval() {
+        let trigger = ScheduleTrigger::Interval { seconds: 90 };
+        assert_eq!(
+            trigger.next_after(time("2024-01-01T00:00:00Z")),
+            Some(time("2024-01-01T00:01:30Z"))
+        );
+    }
+
+    #[test]
+    fn test_interval_bounds() {
+        assert!(ScheduleTrigger::Interval { seconds: 0 }.validate().is_err());
+        assert!(ScheduleTrigger::Interval {
+            seconds: MAX_INTERVAL_SECONDS
+        }
+        .validate()
+        .is_ok());
+        assert!(ScheduleTrigger::Interval {
+            seconds: MAX_INTERVAL_SECONDS + 1
+        }
+        .validate()
+        .is_err());
+
+        let trigger = ScheduleTrigger::Interval { seconds: u64::MAX };
+        assert_eq!(trigger.next_after(time("2024-01-01T00:00:00Z")), None);
+    }
+
+    #[test]
+    fn test_next_after_cron() {
+        let trigger = ScheduleTrigger::Cron {
+            expr: "0 * * * * *".to_string(),
+        };
+        assert_eq!(
+            trigger.next_after(time("2024-01-01T00:00:30Z")),
+            Some(time("2024-01-01T00:01:00Z"))
+        );
+
+        let invalid = ScheduleTrigger::Cron {
+            expr: "not cron".to_string(),
+        };
+        assert_eq!(invalid.next_after(time("2024-01-01T00:00:00Z")), None);
+    }
+
+    #[test]
+    fn test_missed_runs_policy() {
+        let trigger = ScheduleTrigger::Interval { seconds: 60 };
+        let now = time("2024-01-01T00:05:30Z");
+        let mut e = entry(trigger, MissedRunPolicy::RunAll);
+        e.last_run = Some(time("2024-01-01T00:00:00Z"));
+        assert_eq!(missed_runs(&e, now), 5);
+
+        e.missed = MissedRunPolicy::RunOnce;
+        assert_eq!(missed_runs(&e, now), 1);
+
+        e.missed = MissedRunPolicy::Skip;
+        assert_eq!(missed_runs(&e, now), 0);
+    }
+
+    #[test]
+    fn test_missed_runs_since_creation() {
+        let trigger = ScheduleTrigger::Interval { seconds: 60 };
+        let now = time("2024-01-01T00:02:00Z");
+        let mut e = entry(trigger, MissedRunPolicy::RunAll);
+        assert_eq!(missed_runs(&e, now), 0);
+
+        e.created_at = Some(time("2024-01-01T00:00:00Z"));
+        assert_eq!(missed_runs(&e, now), 2);
+    }
+
+    #[test]
+    fn test_missed_runs_limit() {
+        let trigger = ScheduleTrigger::Interval { seconds: 1 };
+        let mut e = entry(trigger, MissedRunPolicy::RunAll);
+        e.last_run = Some(time("2024-01-01T00:00:00Z"));
+        assert_eq!(
+            missed_runs(&e, time("2024-01-02T00:00:00Z")),
+            MAX_MISSED_RUNS
+        );
+    }
+}
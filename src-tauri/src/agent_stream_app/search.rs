@@ -0,0 +1,245 @@
+use std::sync::Mutex;
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_askit::ASKitExt;
+
+use super::action::AppAction;
+use super::app::ASApp;
+use super::settings::CoreSettings;
+
+const DEFAULT_LIMIT: usize = 50;
+/// Config values are long texts such as prompts, so they are matched as substrings
+/// and ranked below fuzzy matches of names.
+const CONFIG_VALUE_SCORE: i64 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Flow,
+    Agent,
+    Node,
+    Action,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SearchAction {
+    Run { action: AppAction },
+    AddAgent { def_name: String },
+    OpenNode { flow: String, node_id: String },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    pub title: String,
+    pub detail: Option<String>,
+    pub score: i64,
+    pub action: SearchAction,
+}
+
+struct Matcher {
+    matcher: SkimMatcherV2,
+    query: String,
+    query_lower: String,
+}
+
+impl Matcher {
+    fn new(query: &str) -> Self {
+        Self {
+            matcher: SkimMatcherV2::default().ignore_case(),
+            query: query.trim().to_string(),
+            query_lower: query.trim().to_lowercase(),
+        }
+    }
+
+    fn score(&self, fields: &[&str]) -> Option<i64> {
+        if self.query.is_empty() {
+            return Some(0);
+        }
+        fields
+            .iter()
+            .enumerate()
+            .filter_map(|(i, field)| {
+                self.matcher
+                    .fuzzy_match(field, &self.query)
+                    .map(|score| score / (i as i64 + 1))
+            })
+            .max()
+    }
+
+    fn contains(&self, text: &str) -> bool {
+        !self.query_lower.is_empty() && text.to_lowercase().contains(&self.query_lower)
+    }
+}
+
+pub fn collect_strings<'a>(value: &'a Value, strings: &mut Vec<&'a str>) {
+    match value {
+        Value::String(s) => strings.push(s),
+        Value::Array(values) => values.iter().for_each(|v| collect_strings(v, strings)),
+        Value::Object(map) => map.values().for_each(|v| collect_strings(v, strings)),
+        _ => {}
+    }
+}
+
+fn search_flows(app: &AppHandle, matcher: &Matcher, results: &mut Vec<SearchResult>) {
+    let defs = serde_json::to_value(app.askit().get_agent_definitions()).unwrap_or_default();
+
+    // Resolved env and file references are not searched
+    for (flow_name, flow) in app.state::<ASApp>().agent_flows_with_refs() {
+        if let Some(score) = matcher.score(&[flow_name.as_str()]) {
+            results.push(SearchResult {
+                kind: SearchKind::Flow,
+                title: flow_name.clone(),
+                detail: None,
+                score,
+                action: SearchAction::Run {
+                    action: AppAction::OpenFlow {
+                        flow: flow_name.clone(),
+                    },
+                },
+            });
+        }
+
+        // Nodes are only listed for a query
+        if matcher.query.is_empty() {
+            continue;
+        }
+        for node in flow
+            .get("nodes")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let Some(id) = node.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+            let def_name = node
+                .get("def_name")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let def_title = defs
+                .get(def_name)
+                .and_then(|def| def.get("title"))
+                .and_then(Value::as_str)
+                .unwrap_or(def_name);
+            let title = node
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or(def_title);
+
+            let mut score = matcher.score(&[title]);
+            if score.is_none() {
+                let mut values = Vec::new();
+                if let Some(configs) = node.get("configs") {
+                    collect_strings(configs, &mut values);
+                }
+                if values.iter().any(|v| matcher.contains(v)) {
+                    score = Some(CONFIG_VALUE_SCORE);
+                }
+            }
+            let Some(score) = score else {
+                continue;
+            };
+            results.push(SearchResult {
+                kind: SearchKind::Node,
+                title: title.to_string(),
+                detail: Some(flow_name.clone()),
+                score,
+                action: SearchAction::OpenNode {
+                    flow: flow_name.clone(),
+                    node_id: id.to_string(),
+                },
+            });
+        }
+    }
+}
+
+fn search_agents(app: &AppHandle, matcher: &Matcher, results: &mut Vec<SearchResult>) {
+    let Ok(defs) = serde_json::to_value(app.askit().get_agent_definitions()) else {
+        return;
+    };
+    for (def_name, def) in defs.as_object().into_iter().flatten() {
+        let title = def.get("title").and_then(Value::as_str).unwrap_or_default();
+        let category = def
+            .get("category")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let Some(score) = matcher.score(&[title, def_name.as_str(), category]) else {
+            continue;
+        };
+        results.push(SearchResult {
+            kind: SearchKind::Agent,
+            title: if title.is_empty() { def_name } else { title }.to_string(),
+            detail: Some(category.to_string()).filter(|c| !c.is_empty()),
+            score,
+            action: SearchAction::AddAgent {
+                def_name: def_name.clone(),
+            },
+        });
+    }
+}
+
+fn app_actions(app: &AppHandle) -> Vec<(String, AppAction)> {
+    let mut actions = vec![
+        ("Show Main Window".to_string(), AppAction::ShowMain),
+        ("Hide Main Window".to_string(), AppAction::HideMain),
+        ("Quick Input".to_string(), AppAction::QuickInput),
+        ("Screenshot".to_string(), AppAction::Screenshot),
+        ("Toggle Fullscreen".to_string(), AppAction::ToggleFullscreen),
+    ];
+
+    let asapp = app.state::<ASApp>();
+    for flow in asapp.agent_flow_names() {
+        let verb = if asapp.is_agent_flow_running(&flow) {
+            "Stop"
+        } else {
+            "Start"
+        };
+        actions.push((format!("{} {}", verb, flow), AppAction::ToggleFlow { flow }));
+    }
+
+    let settings = app.state::<Mutex<CoreSettings>>();
+    let settings = settings.lock().unwrap();
+    for tray_action in settings.tray_actions.iter().flatten() {
+        actions.push((tray_action.label.clone(), tray_action.action.clone()));
+    }
+
+    actions
+}
+
+fn search_actions(app: &AppHandle, matcher: &Matcher, results: &mut Vec<SearchResult>) {
+    for (title, action) in app_actions(app) {
+        let Some(score) = matcher.score(&[title.as_str()]) else {
+            continue;
+        };
+        results.push(SearchResult {
+            kind: SearchKind::Action,
+            title,
+            detail: None,
+            score,
+            action: SearchAction::Run { action },
+        });
+    }
+}
+
+pub fn search(app: &AppHandle, query: &str, limit: usize) -> Vec<SearchResult> {
+    let matcher = Matcher::new(query);
+    let mut results = Vec::new();
+    search_flows(app, &matcher, &mut results);
+    search_agents(app, &matcher, &mut results);
+    search_actions(app, &matcher, &mut results);
+
+    results.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.title.cmp(&b.title)));
+    results.truncate(limit);
+    results
+}
+
+#[tauri::command]
+pub fn search_cmd(app: AppHandle, query: String, limit: Option<usize>) -> Vec<SearchResult> {
+    search(&app, &query, limit.unwrap_or(DEFAULT_LIMIT))
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeSet;
+use std::io::Write as _;
+use std::path::Path;
+
+use anyhow::{bail, Context as _, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_askit::ASKitExt;
+
+const SECRET_KEY_FILE: &str = "secret.key";
+const KEYRING_USER: &str = "secret-key";
+const SECRET_REF_PREFIX: &str = "${secret:";
+const SECRET_REF_SUFFIX: &str = "}";
+const SECRET_CONFIG_TYPE: &str = "password";
+const NONCE_LEN: usize = 12;
+
+pub const SECRET_MASK: &str = "********";
+
+/// The key is kept in the OS keychain. Without one, it is kept in a file readable only by the owner.
+pub struct SecretStore {
+    cipher: ChaCha20Poly1305,
+}
+
+impl SecretStore {
+    pub fn open(app: &AppHandle) -> Result<Self> {
+        let data_dir = app.path().app_data_dir()?;
+        std::fs::create_dir_all(&data_dir)?;
+        let key_path = data_dir.join(SECRET_KEY_FILE);
+
+        let key = match keychain_key(&app.config().identifier, &key_path) {
+            Ok(key) => key,
+            Err(e) => {
+                log::warn!("Failed to use the OS keychain for the secret key: {}", e);
+                if key_path.exists() {
+                    read_key_file(&key_path)?
+                } else {
+                    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+                    write_key_file(&key_path, &key)?;
+                    key
+                }
+            }
+        };
+
+        Ok(SecretStore {
+            cipher: ChaCha20Poly1305::new(&key),
+        })
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt secret"))?;
+
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        Ok(BASE64.encode(data))
+    }
+
+    pub fn decrypt(&self, encoded: &str) -> Result<String> {
+        let data = BASE64.decode(encoded).context("Invalid secret encoding")?;
+        if data.len() < NONCE_LEN {
+            bail!("Invalid secret data");
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt secret"))?;
+        String::from_utf8(plaintext).context("Secret is not valid UTF-8")
+    }
+}
+
+fn keychain_key(service: &str, key_path: &Path) -> Result<Key> {
+    let entry = keyring::Entry::new(service, KEYRING_USER)?;
+    match entry.get_secret() {
+        Ok(bytes) => {
+            if bytes.len() != 32 {
+                bail!("Invalid secret key in the OS keychain");
+            }
+            Ok(*Key::from_slice(&bytes))
+        }
+        Err(keyring::Error::NoEntry) => {
+            // Move the key file of an earlier version into the keychain
+            let key = if key_path.exists() {
+                read_key_file(key_path)?
+            } else {
+                ChaCha20Poly1305::generate_key(&mut OsRng)
+            };
+            entry.set_secret(key.as_slice())?;
+            if key_path.exists() {
+                std::fs::remove_file(key_path).context("Failed to remove secret key file")?;
+            }
+            Ok(key)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn read_key_file(path: &Path) -> Result<Key> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(path)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            log::warn!(
+                "Secret key file {:?} is accessible by other users (mode {:o})",
+                path,
+                mode & 0o777
+            );
+        }
+    }
+
+    let bytes = std::fs::read(path).context("Failed to read secret key file")?;
+    if bytes.len() != 32 {
+        bail!("Invalid secret key file: {:?}", path);
+    }
+    Ok(*Key::from_slice(&bytes))
+}
+
+fn write_key_file(path: &Path, key: &Key) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .context("Failed to create secret key file")?;
+    file.write_all(key.as_slice())?;
+    Ok(())
+}
+
+pub fn secret_ref(agent_name: &str, key: &str) -> String {
+    format!(
+        "{}{}/{}{}",
+        SECRET_REF_PREFIX, agent_name, key, SECRET_REF_SUFFIX
+    )
+}
+
+pub fn parse_secret_ref(value: &str) -> Option<&str> {
+    value
+        .strip_prefix(SECRET_REF_PREFIX)
+        .and_then(|rest| rest.strip_suffix(SECRET_REF_SUFFIX))
+}
+
+pub fn secret_config_keys(app: &AppHandle) -> BTreeSet<(String, String)> {
+    let mut keys = BTreeSet::new();
+
+    let Ok(defs) = serde_json::to_value(app.askit().get_agent_definitions()) else {
+        return keys;
+    };
+    for (agent_name, def) in defs.as_object().into_iter().flatten() {
+        let Some(global_configs) = def.get("global_configs").and_then(|v| v.as_array()) else {
+            continue;
+        };
+        for entry in global_configs {
+            let (Some(key), Some(ty)) = (
+                entry.get(0).and_then(|v| v.as_str()),
+                entry
+                    .get(1)
+                    .and_then(|v| v.get("type"))
+                    .and_then(|v| v.as_str()),
+            ) else {
+                continue;
+            };
+            if ty == SECRET_CONFIG_TYPE {
+                keys.insert((agent_name.clone(), key.to_string()));
+            }
+        }
+    }
+
+    keys
+}
+
+pub fn mask_global_configs(app: &AppHandle, global_configs_map: &mut Value) {
+    for (agent_name, key) in secret_config_keys(app) {
+        if let Some(value) = global_configs_map
+            .get_mut(&agent_name)
+            .and_then(|configs| configs.get_mut(&key))
+        {
+            if value.as_str().is_some_and(|s| !s.is_empty()) {
+                *value = Value::String(SECRET_MASK.to_string());
+            }
+        }
+    }
+}
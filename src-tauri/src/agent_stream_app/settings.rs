@@ -1,24 +1,41 @@
-use agent_stream_kit::AgentValue;
-use anyhow::{Context as _, Result};
+use agent_stream_kit::{AgentConfigs, AgentValue};
+use anyhow::{bail, Context as _, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{
-    collections::HashMap,
-    sync::{LazyLock, Mutex},
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, LazyLock, Mutex},
 };
-use tauri::{AppHandle, Manager, State};
+use tauri::{AppHandle, Manager, State, Wry};
 use tauri_plugin_askit::ASKitExt;
-use tauri_plugin_store::StoreExt;
+use tauri_plugin_store::{Store, StoreExt};
 
-const SETTINGS_JSON: &str = "settings.json";
+use super::action::AppAction;
+use super::config_ref::{self, ConfigRefs};
+use super::secret::{self, SecretStore, SECRET_MASK};
+
+const QUARANTINE_KEY: &str = "quarantine";
+const SECRETS_KEY: &str = "secrets";
+
+pub const DEFAULT_ERROR_NOTIFICATION_INTERVAL_SECS: u64 = 60;
 
 pub fn init(app: &AppHandle) -> Result<()> {
     init_core_settings(app)?;
+    super::logging::update(app).unwrap_or_else(|e| {
+        log::error!("Failed to apply log settings: {}", e);
+    });
+    app.manage(ConfigRefs::default());
+    app.manage(SecretStore::open(app).context("Failed to open secret store")?);
     Ok(())
 }
 
+pub fn settings_store(app: &AppHandle) -> Result<Arc<Store<Wry>>> {
+    let store = app.store(super::profile::current_settings_json(app))?;
+    Ok(store)
+}
+
 pub fn save(app: &AppHandle) -> Result<()> {
-    let store = app.store(SETTINGS_JSON)?;
+    let store = settings_store(app)?;
 
     let core_settings = app.state::<Mutex<CoreSettings>>();
     let settings_json;
@@ -28,23 +45,115 @@ pub fn save(app: &AppHandle) -> Result<()> {
     }
     store.set("core", settings_json);
 
-    let agent_settings = app.askit().get_global_configs_map();
-    let agent_settings_json = serde_json::to_value(agent_settings)?;
+    let mut agent_settings_json = agent_global_configs_json(app)?;
+    let secrets = seal_secrets(app, &mut agent_settings_json)?;
     store.set("agents", agent_settings_json);
+    store.set(SECRETS_KEY, secrets);
 
     Ok(())
 }
 
+/// The agent global configs map as JSON, with env and file references in place of their values.
+pub fn agent_global_configs_json(app: &AppHandle) -> Result<Value> {
+    let agent_settings = app.askit().get_global_configs_map();
+    let mut agent_settings_json = serde_json::to_value(agent_settings)?;
+    let config_refs = app.state::<ConfigRefs>();
+    for (agent_name, configs) in agent_settings_json.as_object_mut().into_iter().flatten() {
+        config_refs.restore_configs(agent_name, configs);
+    }
+    Ok(agent_settings_json)
+}
+
+/// Move secret values out of the agent settings into an encrypted map,
+/// leaving a secret reference in their place.
+fn seal_secrets(app: &AppHandle, agent_settings: &mut Value) -> Result<Value> {
+    let secret_store = app.state::<SecretStore>();
+    let mut secrets = serde_json::Map::new();
+
+    for (agent_name, key) in secret::secret_config_keys(app) {
+        let Some(value) = agent_settings
+            .get_mut(&agent_name)
+            .and_then(|configs| configs.get_mut(&key))
+        else {
+            continue;
+        };
+        let Some(plaintext) = value
+            .as_str()
+            .filter(|s| !s.is_empty() && !config_ref::has_reference(s))
+        else {
+            continue;
+        };
+        let secret_ref = secret::secret_ref(&agent_name, &key);
+        let id = secret::parse_secret_ref(&secret_ref).unwrap_or_default();
+        secrets.insert(
+            id.to_string(),
+            Value::String(secret_store.encrypt(plaintext)?),
+        );
+        *value = Value::String(secret_ref);
+    }
+
+    Ok(Value::Object(secrets))
+}
+
+fn open_secret(app: &AppHandle, store: &Store<Wry>, value: &Value) -> Result<Option<Value>> {
+    let Some(id) = value.as_str().and_then(secret::parse_secret_ref) else {
+        return Ok(None);
+    };
+    let encrypted = store
+        .get(SECRETS_KEY)
+        .and_then(|secrets| secrets.get(id).and_then(|v| v.as_str()).map(str::to_string))
+        .with_context(|| format!("Secret not found: {}", id))?;
+    let plaintext = app.state::<SecretStore>().decrypt(&encrypted)?;
+    Ok(Some(Value::String(plaintext)))
+}
+
 pub fn quit(_app: &AppHandle) {
     // save(app);
 }
 
 // core settings
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CoreSettings {
     pub autostart: Option<bool>,
     pub shortcut_keys: Option<HashMap<String, String>>,
+    pub global_shortcuts: Option<Vec<ShortcutBinding>>,
+    pub log_levels: Option<HashMap<String, String>>,
+    pub log_retention_days: Option<u32>,
+    pub metrics_port: Option<u16>,
+    pub tray_actions: Option<Vec<TrayAction>>,
+    pub notify_errors: Option<bool>,
+    pub error_notification_interval_secs: Option<u64>,
+    pub quick_input: Option<QuickInputSettings>,
+    pub allow_deep_link_run: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShortcutBinding {
+    pub keys: String,
+    pub action: AppAction,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrayAction {
+    pub label: String,
+    pub action: AppAction,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuickInputSettings {
+    pub flow: String,
+    pub node: String,
+    #[serde(default = "default_quick_input_ch")]
+    pub ch: String,
+    #[serde(default)]
+    pub display_node: Option<String>,
+    #[serde(default)]
+    pub on_global_shortcut: bool,
+}
+
+pub fn default_quick_input_ch() -> String {
+    "in".to_string()
 }
 
 impl Default for CoreSettings {
@@ -68,42 +177,335 @@ impl Default for CoreSettings {
         CoreSettings {
             autostart: Some(false),
             shortcut_keys: Some(SHORTCUT_KEYS.clone()),
+            global_shortcuts: Some(Vec::new()),
+            log_levels: Some(HashMap::new()),
+            log_retention_days: Some(14),
+            metrics_port: None,
+            tray_actions: Some(Vec::new()),
+            notify_errors: Some(false),
+            error_notification_interval_secs: Some(DEFAULT_ERROR_NOTIFICATION_INTERVAL_SECS),
+            quick_input: None,
+            allow_deep_link_run: Some(false),
         }
     }
 }
 
 fn init_core_settings(app: &AppHandle) -> Result<()> {
-    let store = app.store(SETTINGS_JSON)?;
+    let core_settings = read_core_settings(app)?;
+    app.manage(Mutex::new(core_settings));
+    Ok(())
+}
 
-    let core_settings: CoreSettings;
+fn read_core_settings(app: &AppHandle) -> Result<CoreSettings> {
+    let store = settings_store(app)?;
+
+    let mut core_settings = CoreSettings::default();
     if let Some(store_value) = store.get("core") {
-        let mut value = serde_json::to_value(CoreSettings::default())
-            .context("Failed to serialize default core settings")?;
-        json_merge(&mut value, store_value.clone());
+        let (settings, errors) = merge_core_settings(&core_settings, store_value.clone())
+            .context("Failed to merge core settings")?;
+        core_settings = settings;
 
-        core_settings = serde_json::from_value(value).unwrap_or_else(|e| {
-            log::error!("Failed to load core settings: {}", e);
-            CoreSettings::default()
-        });
-    } else {
-        core_settings = CoreSettings::default();
+        // Keep the values we could not load instead of discarding them
+        if !errors.is_empty() {
+            let mut quarantine = store.get(QUARANTINE_KEY).unwrap_or(Value::Null);
+            if !quarantine.is_object() {
+                quarantine = Value::Object(Default::default());
+            }
+            let core_quarantine = quarantine
+                .as_object_mut()
+                .unwrap()
+                .entry("core")
+                .or_insert(Value::Object(Default::default()));
+            for (key, message) in &errors {
+                log::error!("Failed to load core setting {}: {}", key, message);
+                if let (Some(core_quarantine), Some(raw)) =
+                    (core_quarantine.as_object_mut(), store_value.get(key))
+                {
+                    core_quarantine.insert(key.clone(), raw.clone());
+                }
+            }
+            store.set(QUARANTINE_KEY, quarantine);
+        }
     }
 
-    app.manage(Mutex::new(core_settings));
+    Ok(core_settings)
+}
+
+pub fn reload(app: &AppHandle) -> Result<(), SettingsError> {
+    let new_settings = read_core_settings(app).map_err(|e| SettingsError::new(e.to_string()))?;
+    let old_settings;
+    {
+        let settings = app.state::<Mutex<CoreSettings>>();
+        let mut settings = settings.lock().unwrap();
+        old_settings = settings.clone();
+        *settings = new_settings.clone();
+    }
 
+    load_agent_global_configs(app).map_err(|e| SettingsError::new(e.to_string()))?;
+
+    apply_core_settings(app, &old_settings, &new_settings)
+}
+
+// core settings schema
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SettingsField {
+    pub key: &'static str,
+    pub kind: &'static str,
+    pub default: Value,
+    pub allowed: Option<Vec<Value>>,
+    pub description: &'static str,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SettingsError {
+    pub message: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+impl SettingsError {
+    fn new(message: impl Into<String>) -> Self {
+        SettingsError {
+            message: message.into(),
+            fields: BTreeMap::new(),
+        }
+    }
+}
+
+pub fn core_settings_schema() -> Vec<SettingsField> {
+    let defaults = serde_json::to_value(CoreSettings::default()).unwrap_or_default();
+    let default_of = |key: &str| defaults.get(key).cloned().unwrap_or(Value::Null);
+
+    vec![
+        SettingsField {
+            key: "autostart",
+            kind: "boolean",
+            default: default_of("autostart"),
+            allowed: None,
+            description: "Launch Agent Stream App when you log in.",
+        },
+        SettingsField {
+            key: "shortcut_keys",
+            kind: "map<string, string>",
+            default: default_of("shortcut_keys"),
+            allowed: Some(
+                ["global_shortcut", "fullscreen", "screenshot_only", "search"]
+                    .into_iter()
+                    .map(Value::from)
+                    .collect(),
+            ),
+            description: "Keyboard shortcuts. Multiple keys are separated by commas. \
+                They are registered system-wide. global_shortcut shows the main window, \
+                and the others run the screenshot, toggle_fullscreen and search actions.",
+        },
+        SettingsField {
+            key: "global_shortcuts",
+            kind: "array<{ keys: string, action: object }>",
+            default: default_of("global_shortcuts"),
+            allowed: Some(
+                [
+                    "show_main",
+                    "hide_main",
+                    "toggle_main",
+                    "toggle_flow",
+                    "send_to_input",
+                    "send_to_agent",
+                    "screenshot",
+                    "toggle_fullscreen",
+                    "search",
+                    "quick_input",
+                    "open_flow",
+                    "run_flow",
+                    "import_flow",
+                ]
+                .into_iter()
+                .map(Value::from)
+                .collect(),
+            ),
+            description: "System-wide shortcuts bound to actions. \
+                The action type must be one of the allowed values.",
+        },
+        SettingsField {
+            key: "tray_actions",
+            kind: "array<{ label: string, action: object }>",
+            default: default_of("tray_actions"),
+            allowed: None,
+            description: "Actions listed in the Actions menu of the tray icon, \
+                such as sending a test value to a node.",
+        },
+        SettingsField {
+            key: "notify_errors",
+            kind: "boolean",
+            default: default_of("notify_errors"),
+            allowed: None,
+            description: "Show a desktop notification when an agent reports an error.",
+        },
+        SettingsField {
+            key: "error_notification_interval_secs",
+            kind: "integer",
+            default: default_of("error_notification_interval_secs"),
+            allowed: None,
+            description: "Minimum number of seconds between error notifications of the same agent.",
+        },
+        SettingsField {
+            key: "quick_input",
+            kind: "{ flow: string, node: string, ch?: string, display_node?: string, \
+                on_global_shortcut?: boolean }",
+            default: default_of("quick_input"),
+            allowed: None,
+            description: "Flow node the quick-input overlay sends to, and the display node \
+                shown as the reply. With on_global_shortcut, global_shortcut opens the overlay.",
+        },
+        SettingsField {
+            key: "allow_deep_link_run",
+            kind: "boolean",
+            default: default_of("allow_deep_link_run"),
+            allowed: None,
+            description: "Allow askit://run links to start flows and send them input. \
+                Any web page can open these links.",
+        },
+        SettingsField {
+            key: "log_levels",
+            kind: "map<string, string>",
+            default: default_of("log_levels"),
+            allowed: Some(
+                ["off", "error", "warn", "info", "debug", "trace"]
+                    .into_iter()
+                    .map(Value::from)
+                    .collect(),
+            ),
+            description: "Log level by target, such as \"agent_stream_app_lib\". \
+                The empty target sets the default level.",
+        },
+        SettingsField {
+            key: "log_retention_days",
+            kind: "integer",
+            default: default_of("log_retention_days"),
+            allowed: None,
+            description: "Log files older than this number of days are removed.",
+        },
+        SettingsField {
+            key: "metrics_port",
+            kind: "integer",
+            default: default_of("metrics_port"),
+            allowed: None,
+            description: "Port of the Prometheus metrics endpoint on localhost. \
+                The endpoint is disabled when not set.",
+        },
+    ]
+}
+
+/// Fields that fail to validate are left unchanged and reported by key.
+pub fn merge_core_settings(
+    settings: &CoreSettings,
+    new_value: Value,
+) -> Result<(CoreSettings, BTreeMap<String, String>)> {
+    let Value::Object(new_fields) = new_value else {
+        bail!("Invalid settings format");
+    };
+
+    let schema = core_settings_schema();
+    let mut value = serde_json::to_value(settings).context("Failed to serialize core settings")?;
+    let mut errors = BTreeMap::new();
+
+    for (key, field_value) in new_fields {
+        if !schema.iter().any(|field| field.key == key) {
+            errors.insert(key, "Unknown setting".to_string());
+            continue;
+        }
+
+        let mut candidate = value.clone();
+        let mut patch = serde_json::Map::new();
+        patch.insert(key.clone(), field_value);
+        json_merge(&mut candidate, Value::Object(patch));
+
+        match serde_json::from_value::<CoreSettings>(candidate.clone())
+            .map_err(anyhow::Error::from)
+            .and_then(|candidate_settings| validate_field(&key, &candidate_settings))
+        {
+            Ok(()) => value = candidate,
+            Err(e) => {
+                errors.insert(key, e.to_string());
+            }
+        }
+    }
+
+    let settings = serde_json::from_value(value).context("Failed to deserialize core settings")?;
+    Ok((settings, errors))
+}
+
+fn validate_field(key: &str, settings: &CoreSettings) -> Result<()> {
+    match key {
+        "shortcut_keys" => {
+            if let Some(keys) = settings
+                .shortcut_keys
+                .as_ref()
+                .and_then(|keys| keys.get("global_shortcut"))
+            {
+                super::shortcut::parse_shortcuts(keys)?;
+            }
+        }
+        "global_shortcuts" => {
+            for binding in settings.global_shortcuts.iter().flatten() {
+                super::shortcut::parse_shortcuts(&binding.keys)?;
+            }
+        }
+        "log_levels" => {
+            for level in settings.log_levels.iter().flatten().map(|(_, level)| level) {
+                super::logging::parse_level(level)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+pub fn remove_from_quarantine(app: &AppHandle, keys: &[String]) -> Result<()> {
+    let store = settings_store(app)?;
+    if let Some(mut quarantine) = store.get(QUARANTINE_KEY) {
+        if let Some(core_quarantine) = quarantine.get_mut("core").and_then(|v| v.as_object_mut()) {
+            for key in keys {
+                core_quarantine.remove(key);
+            }
+        }
+        store.set(QUARANTINE_KEY, quarantine);
+    }
     Ok(())
 }
 
+struct DefaultGlobalConfigs(HashMap<String, AgentConfigs>);
+
 pub fn load_agent_global_configs(app: &AppHandle) -> Result<()> {
-    let store = app.store(SETTINGS_JSON)?;
+    let store = settings_store(app)?;
+
+    // The first call happens right after the agents are registered.
+    if app.try_state::<DefaultGlobalConfigs>().is_none() {
+        app.manage(DefaultGlobalConfigs(app.askit().get_global_configs_map()));
+    }
+    let default_configs_map = app.state::<DefaultGlobalConfigs>().0.clone();
+
+    let config_refs = app.state::<ConfigRefs>();
+    config_refs.clear();
 
     if let Some(store_value) = store.get("agents") {
-        let mut global_configs_map = app.askit().get_global_configs_map();
+        let mut global_configs_map = default_configs_map;
         for (agent_name, configs) in store_value.as_object().unwrap_or(&Default::default()) {
             if let Some(agent_configs) = global_configs_map.get_mut(agent_name) {
+                // Resolve env and file references
+                let mut configs = configs.clone();
+                config_refs.resolve_configs(agent_name, &mut configs);
+
                 for (key, value) in configs.as_object().unwrap_or(&Default::default()) {
                     if agent_configs.contains_key(key) {
-                        if let Ok(value) = AgentValue::from_json(value.clone()) {
+                        let value = match open_secret(app, &store, value) {
+                            Ok(Some(secret)) => secret,
+                            Ok(None) => value.clone(),
+                            Err(e) => {
+                                log::error!("Failed to load secret {}.{}: {}", agent_name, key, e);
+                                continue;
+                            }
+                        };
+                        if let Ok(value) = AgentValue::from_json(value) {
                             agent_configs.set(key.clone(), value);
                         }
                     }
@@ -111,11 +513,129 @@ pub fn load_agent_global_configs(app: &AppHandle) -> Result<()> {
             }
         }
         app.askit().set_global_configs_map(global_configs_map);
+    } else {
+        app.askit().set_global_configs_map(default_configs_map);
+    }
+
+    for (agent_name, keys) in stale_global_configs(app)? {
+        log::warn!(
+            "Unknown global configs of {}: {}",
+            agent_name,
+            keys.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Keys the agent doesn't have are skipped, as on load, and returned.
+pub fn set_global_configs(
+    app: &AppHandle,
+    agent_name: &str,
+    configs: &Value,
+) -> Result<Vec<String>> {
+    let store = settings_store(app)?;
+    let secret_keys = secret::secret_config_keys(app);
+    let mut global_configs_map = app.askit().get_global_configs_map();
+    let agent_configs = global_configs_map
+        .get_mut(agent_name)
+        .with_context(|| format!("Agent not found: {}", agent_name))?;
+
+    // Resolve env and file references as on load
+    let mut configs = configs.clone();
+    app.state::<ConfigRefs>()
+        .resolve_configs(agent_name, &mut configs);
+
+    let mut unknown_keys = Vec::new();
+    for (key, value) in configs.as_object().context("Invalid configs format")? {
+        if !agent_configs.contains_key(key) {
+            unknown_keys.push(key.clone());
+            continue;
+        }
+        // The masked value means the secret was not changed in the frontend.
+        if value.as_str() == Some(SECRET_MASK)
+            && secret_keys.contains(&(agent_name.to_string(), key.clone()))
+        {
+            continue;
+        }
+        let value = open_secret(app, &store, value)?.unwrap_or_else(|| value.clone());
+        let value = AgentValue::from_json(value)?;
+        agent_configs.set(key.clone(), value);
+    }
+    app.askit().set_global_configs_map(global_configs_map);
+
+    if !unknown_keys.is_empty() {
+        log::warn!(
+            "Unknown global configs of {}: {}",
+            agent_name,
+            unknown_keys.join(", ")
+        );
+    }
+    Ok(unknown_keys)
+}
+
+pub fn reset_global_configs(app: &AppHandle, agent_name: &str, key: Option<&str>) -> Result<()> {
+    let default_configs = app
+        .state::<DefaultGlobalConfigs>()
+        .0
+        .get(agent_name)
+        .cloned()
+        .with_context(|| format!("Agent not found: {}", agent_name))?;
+
+    let mut global_configs_map = app.askit().get_global_configs_map();
+    match key {
+        None => {
+            global_configs_map.insert(agent_name.to_string(), default_configs);
+        }
+        Some(key) => {
+            let default_value = serde_json::to_value(&default_configs)?
+                .get(key)
+                .cloned()
+                .with_context(|| format!("Config not found: {}.{}", agent_name, key))?;
+            let agent_configs = global_configs_map
+                .get_mut(agent_name)
+                .with_context(|| format!("Agent not found: {}", agent_name))?;
+            agent_configs.set(key.to_string(), AgentValue::from_json(default_value)?);
+        }
+    }
+    app.askit().set_global_configs_map(global_configs_map);
+
+    // Forget the references so that the default value is saved as is.
+    let config_refs = app.state::<ConfigRefs>();
+    match key {
+        None => config_refs.remove_owner(agent_name),
+        Some(key) => config_refs.remove(agent_name, key),
     }
 
     Ok(())
 }
 
+pub fn stale_global_configs(app: &AppHandle) -> Result<BTreeMap<String, Vec<String>>> {
+    let store = settings_store(app)?;
+    let default_configs_map = serde_json::to_value(&app.state::<DefaultGlobalConfigs>().0)?;
+
+    let mut stale = BTreeMap::new();
+    let Some(store_value) = store.get("agents") else {
+        return Ok(stale);
+    };
+    for (agent_name, configs) in store_value.as_object().into_iter().flatten() {
+        let default_configs = default_configs_map.get(agent_name);
+        let keys: Vec<String> = configs
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(key, _)| key)
+            .filter(|key| default_configs.and_then(|c| c.get(key.as_str())).is_none())
+            .cloned()
+            .collect();
+        if !keys.is_empty() {
+            stale.insert(agent_name.clone(), keys);
+        }
+    }
+
+    Ok(stale)
+}
+
 fn json_merge(a: &mut Value, b: Value) {
     if let Value::Object(a) = a {
         if let Value::Object(b) = b {
@@ -144,24 +664,148 @@ pub fn set_core_settings_cmd(
     app: AppHandle,
     settings: State<Mutex<CoreSettings>>,
     new_settings: Value,
-) -> Result<(), String> {
+) -> Result<(), SettingsError> {
     if new_settings.is_null() {
         return Ok(());
     }
 
+    let keys: Vec<String> = new_settings
+        .as_object()
+        .map(|fields| fields.keys().cloned().collect())
+        .unwrap_or_default();
+
     // Merge new settings into existing settings
-    if new_settings.is_object() {
+    let old_settings;
+    let updated_settings;
+    {
         let mut settings = settings.lock().unwrap();
-        let mut value = serde_json::to_value(&*settings)
-            .map_err(|e| format!("Failed to serialize current settings: {}", e))?;
-        json_merge(&mut value, new_settings);
-        *settings = serde_json::from_value(value)
-            .map_err(|e| format!("Failed to deserialize new settings: {}", e))?;
-    } else {
-        return Err("Invalid settings format".to_string());
+        old_settings = settings.clone();
+        let (merged, errors) = merge_core_settings(&settings, new_settings)
+            .map_err(|e| SettingsError::new(e.to_string()))?;
+        if !errors.is_empty() {
+            return Err(SettingsError {
+                message: "Invalid settings".to_string(),
+                fields: errors,
+            });
+        }
+        *settings = merged;
+        updated_settings = settings.clone();
     }
 
-    save(&app).map_err(|e| e.to_string())?;
+    save(&app).map_err(|e| SettingsError::new(e.to_string()))?;
+    remove_from_quarantine(&app, &keys).unwrap_or_else(|e| {
+        log::error!("Failed to update quarantined settings: {}", e);
+    });
+
+    apply_core_settings(&app, &old_settings, &updated_settings)?;
 
     Ok(())
 }
+
+#[tauri::command]
+pub fn get_global_configs_map_cmd(app: AppHandle) -> Result<Value, String> {
+    let global_configs_map = app.askit().get_global_configs_map();
+    let mut json = serde_json::to_value(global_configs_map).map_err(|e| e.to_string())?;
+    secret::mask_global_configs(&app, &mut json);
+    Ok(json)
+}
+
+#[tauri::command]
+pub fn set_global_configs_cmd(
+    app: AppHandle,
+    agent_name: String,
+    configs: Value,
+) -> Result<Vec<String>, String> {
+    let unknown_keys =
+        set_global_configs(&app, &agent_name, &configs).map_err(|e| e.to_string())?;
+    save(&app).map_err(|e| e.to_string())?;
+    Ok(unknown_keys)
+}
+
+#[tauri::command]
+pub fn reset_global_configs_cmd(
+    app: AppHandle,
+    agent_name: String,
+    key: Option<String>,
+) -> Result<Value, String> {
+    reset_global_configs(&app, &agent_name, key.as_deref()).map_err(|e| e.to_string())?;
+    save(&app).map_err(|e| e.to_string())?;
+
+    let mut json =
+        serde_json::to_value(app.askit().get_global_configs_map()).map_err(|e| e.to_string())?;
+    secret::mask_global_configs(&app, &mut json);
+    Ok(json.get(&agent_name).cloned().unwrap_or(Value::Null))
+}
+
+#[tauri::command]
+pub fn get_stale_global_configs_cmd(
+    app: AppHandle,
+) -> Result<BTreeMap<String, Vec<String>>, String> {
+    stale_global_configs(&app).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_core_settings_schema_cmd() -> Vec<SettingsField> {
+    core_settings_schema()
+}
+
+#[tauri::command]
+pub fn get_quarantined_settings_cmd(app: AppHandle) -> Result<Value, String> {
+    let store = settings_store(&app).map_err(|e| e.to_string())?;
+    Ok(store.get(QUARANTINE_KEY).unwrap_or(Value::Null))
+}
+
+/// Every change is applied even if an earlier one fails,
+/// and all failures are reported by setting name.
+pub fn apply_core_settings(
+    app: &AppHandle,
+    old_settings: &CoreSettings,
+    new_settings: &CoreSettings,
+) -> Result<(), SettingsError> {
+    let mut errors = BTreeMap::new();
+
+    if old_settings.autostart != new_settings.autostart {
+        super::autostart::update(app).unwrap_or_else(|e| {
+            errors.insert("autostart".to_string(), e.to_string());
+        });
+    }
+
+    if old_settings.shortcut_keys != new_settings.shortcut_keys
+        || old_settings.global_shortcuts != new_settings.global_shortcuts
+        || old_settings.quick_input != new_settings.quick_input
+    {
+        super::shortcut::update(app).unwrap_or_else(|e| {
+            errors.insert("shortcut_keys".to_string(), e.to_string());
+        });
+    }
+
+    if old_settings.log_levels != new_settings.log_levels
+        || old_settings.log_retention_days != new_settings.log_retention_days
+    {
+        super::logging::update(app).unwrap_or_else(|e| {
+            errors.insert("log_levels".to_string(), e.to_string());
+        });
+    }
+
+    if old_settings.metrics_port != new_settings.metrics_port {
+        super::metrics::update(app).unwrap_or_else(|e| {
+            errors.insert("metrics_port".to_string(), e.to_string());
+        });
+    }
+
+    if old_settings.tray_actions != new_settings.tray_actions {
+        super::tray::update(app);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        for (key, e) in &errors {
+            log::error!("Failed to apply core setting {}: {}", key, e);
+        }
+        Err(SettingsError {
+            message: "Failed to apply settings".to_string(),
+            fields: errors,
+        })
+    }
+}
@@ -1,46 +1,136 @@
 use std::sync::Mutex;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use tauri::{AppHandle, Manager};
-use tauri_plugin_global_shortcut::{Shortcut, ShortcutState};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
-use crate::agent_stream_app;
+use crate::agent_stream_app::action::{self, AppAction};
 use crate::agent_stream_app::settings::CoreSettings;
 
+#[derive(Default)]
+struct ShortcutBindings(Mutex<Vec<(Shortcut, AppAction)>>);
+
 pub fn init(app: &AppHandle) -> Result<()> {
+    app.manage(ShortcutBindings::default());
+
+    app.plugin(
+        tauri_plugin_global_shortcut::Builder::new()
+            .with_handler(move |app, key, event| {
+                log::info!("handle shortcut {:?} {:?}", key, event);
+                if event.state != ShortcutState::Pressed {
+                    return;
+                }
+                let bindings = app.state::<ShortcutBindings>();
+                let actions: Vec<AppAction> = bindings
+                    .0
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|(shortcut, _)| shortcut == key)
+                    .map(|(_, action)| action.clone())
+                    .collect();
+                for action in actions {
+                    action::run(app, &action).unwrap_or_else(|e| {
+                        log::error!("Failed to run action {:?}: {}", action, e);
+                    });
+                }
+            })
+            .build(),
+    )?;
+
+    update(app)
+}
+
+/// A binding that fails to parse or register is skipped, and the others are still registered.
+pub fn update(app: &AppHandle) -> Result<()> {
+    let (bindings, mut errors) = shortcut_bindings(app);
+
+    let global_shortcut = app.global_shortcut();
+    global_shortcut.unregister_all()?;
+
+    let mut registered: Vec<(Shortcut, AppAction)> = Vec::new();
+    for (keys, shortcut, action) in bindings {
+        if !registered.iter().any(|(s, _)| s == &shortcut) {
+            log::info!("register shortcut: {:?}", shortcut);
+            if let Err(e) = global_shortcut.register(shortcut) {
+                errors.push(format!("{}: {}", keys, e));
+                continue;
+            }
+        }
+        registered.push((shortcut, action));
+    }
+
+    *app.state::<ShortcutBindings>().0.lock().unwrap() = registered;
+
+    if !errors.is_empty() {
+        bail!("Failed to register shortcuts: {}", errors.join("; "));
+    }
+    Ok(())
+}
+
+fn shortcut_bindings(app: &AppHandle) -> (Vec<(String, Shortcut, AppAction)>, Vec<String>) {
     let settings = app.state::<Mutex<CoreSettings>>();
-    let shortcut_key;
+    let settings = settings.lock().unwrap();
+
+    let mut bindings = Vec::new();
+    let mut errors = Vec::new();
+    let mut add = |keys: &str, action: &AppAction| {
+        for key in split_shortcuts(keys) {
+            match Shortcut::try_from(key) {
+                Ok(shortcut) => bindings.push((key.to_string(), shortcut, action.clone())),
+                Err(e) => errors.push(format!("{}: {}", key, e)),
+            }
+        }
+    };
+
+    // "global_shortcut" is kept for compatibility and shows the main window,
+    // or the quick-input overlay if it is configured to.
+    if let Some(keys) = settings
+        .shortcut_keys
+        .as_ref()
+        .and_then(|keys| keys.get("global_shortcut"))
     {
-        let settings = settings.lock().unwrap();
-        shortcut_key = settings
-            .shortcut_keys
+        let action = if settings
+            .quick_input
             .as_ref()
-            .and_then(|keys| keys.get("global_shortcut").cloned());
+            .is_some_and(|q| q.on_global_shortcut)
+        {
+            AppAction::QuickInput
+        } else {
+            AppAction::ShowMain
+        };
+        add(keys, &action);
     }
-    if let Some(shortcut_key) = shortcut_key {
-        if shortcut_key.is_empty() {
-            return Ok(());
+
+    for (name, action) in [
+        ("screenshot_only", AppAction::Screenshot),
+        ("fullscreen", AppAction::ToggleFullscreen),
+        ("search", AppAction::Search),
+    ] {
+        if let Some(keys) = settings
+            .shortcut_keys
+            .as_ref()
+            .and_then(|keys| keys.get(name))
+        {
+            add(keys, &action);
         }
+    }
 
-        let shortcut = Shortcut::try_from(shortcut_key)?;
-        log::info!("register shortcut: {:?}", shortcut);
-
-        app.plugin(
-            tauri_plugin_global_shortcut::Builder::new()
-                .with_shortcut(shortcut)?
-                .with_handler(move |app, key, event| {
-                    log::info!("handle shortcut {:?} {:?}", key, event);
-                    if event.state == ShortcutState::Pressed {
-                        if key == &shortcut {
-                            agent_stream_app::window::show_main(app).unwrap_or_else(|e| {
-                                log::error!("Failed to show main window: {}", e);
-                            });
-                        }
-                    }
-                })
-                .build(),
-        )?;
+    for binding in settings.global_shortcuts.iter().flatten() {
+        add(&binding.keys, &binding.action);
     }
 
-    Ok(())
+    (bindings, errors)
+}
+
+fn split_shortcuts(keys: &str) -> impl Iterator<Item = &str> {
+    keys.split(',').map(str::trim).filter(|key| !key.is_empty())
+}
+
+pub fn parse_shortcuts(keys: &str) -> Result<Vec<Shortcut>> {
+    let mut shortcuts = Vec::new();
+    for key in split_shortcuts(keys) {
+        shortcuts.push(Shortcut::try_from(key)?);
+    }
+    Ok(shortcuts)
 }
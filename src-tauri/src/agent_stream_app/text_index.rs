@@ -0,0 +1,485 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context as _, Result};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+
+use super::app::ASApp;
+use super::search::collect_strings;
+use super::topology::Topology;
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_OUTPUT_LEN: usize = 16 * 1024;
+const SNIPPET_CONTEXT: usize = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocSource {
+    Flow,
+    Output,
+}
+
+/// A field of a node. Nodes are referred to by their ids in the flow file,
+/// since node ids change when a flow is loaded.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct DocKey {
+    pub source: DocSource,
+    pub flow: String,
+    pub node: String,
+    pub field: String,
+}
+
+struct Doc {
+    title: String,
+    text: String,
+    tokens: HashSet<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TextSearchHit {
+    #[serde(flatten)]
+    pub key: DocKey,
+    pub title: String,
+    pub snippet: String,
+    pub score: usize,
+}
+
+#[derive(Default)]
+pub struct TextIndex {
+    state: Mutex<TextIndexState>,
+    topology: Mutex<Topology>,
+}
+
+#[derive(Default)]
+struct TextIndexState {
+    docs: HashMap<DocKey, Doc>,
+    postings: HashMap<String, HashSet<DocKey>>,
+    flows_dir: PathBuf,
+    _watcher: Option<RecommendedWatcher>,
+}
+
+/// Lowercase words. Underscores are kept, so that function names such as `call_llm` stay one word.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+}
+
+impl TextIndexState {
+    fn insert(&mut self, key: DocKey, title: String, text: String) {
+        self.remove(&key);
+        if text.trim().is_empty() {
+            return;
+        }
+        let tokens: HashSet<String> = tokenize(&text).collect();
+        for token in &tokens {
+            self.postings
+                .entry(token.clone())
+                .or_default()
+                .insert(key.clone());
+        }
+        self.docs.insert(
+            key,
+            Doc {
+                title,
+                text,
+                tokens,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &DocKey) {
+        let Some(doc) = self.docs.remove(key) else {
+            return;
+        };
+        for token in &doc.tokens {
+            if let Some(keys) = self.postings.get_mut(token) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.postings.remove(token);
+                }
+            }
+        }
+    }
+
+    fn remove_where(&mut self, f: impl Fn(&DocKey) -> bool) {
+        let keys: Vec<DocKey> = self.docs.keys().filter(|key| f(key)).cloned().collect();
+        for key in &keys {
+            self.remove(key);
+        }
+    }
+
+    fn flow_name(&self, path: &Path) -> Option<String> {
+        if path.extension().unwrap_or_default() != "json" {
+            return None;
+        }
+        let relative = path.strip_prefix(&self.flows_dir).ok()?.with_extension("");
+        let components: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        Some(components.join("/"))
+    }
+
+    fn index_flow_file(&mut self, path: &Path) -> Result<()> {
+        let Some(flow_name) = self.flow_name(path) else {
+            // A removed folder takes its flows with it
+            if !path.exists() {
+                if let Ok(relative) = path.strip_prefix(&self.flows_dir) {
+                    let prefix = format!("{}/", relative.to_string_lossy().replace('\\', "/"));
+                    self.remove_where(|key| {
+                        key.source == DocSource::Flow && key.flow.starts_with(&prefix)
+                    });
+                }
+            }
+            return Ok(());
+        };
+        self.remove_where(|key| key.source == DocSource::Flow && key.flow == flow_name);
+        if !path.is_file() {
+            return Ok(());
+        }
+
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        let flow: Value = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid agent flow file: {:?}", path))?;
+        for node in flow
+            .get("nodes")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let field = |key: &str| {
+                node.get(key)
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let id = field("id");
+            let def_name = field("def_name");
+            let title = field("title");
+            let node_title = if title.is_empty() {
+                def_name.clone()
+            } else {
+                title.clone()
+            };
+            let key = |field: &str| DocKey {
+                source: DocSource::Flow,
+                flow: flow_name.clone(),
+                node: id.clone(),
+                field: field.to_string(),
+            };
+
+            self.insert(key("def_name"), node_title.clone(), def_name.clone());
+            self.insert(key("title"), node_title.clone(), title.clone());
+            for (config_key, value) in node
+                .get("configs")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+            {
+                let mut strings = Vec::new();
+                collect_strings(value, &mut strings);
+                self.insert(key(config_key), node_title.clone(), strings.join("\n"));
+            }
+        }
+        Ok(())
+    }
+
+    fn index_flows_dir(&mut self, dir: &Path) -> Result<()> {
+        if !dir.is_dir() {
+            return Ok(());
+        }
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.index_flows_dir(&path)?;
+            } else {
+                self.index_flow_file(&path).unwrap_or_else(|e| {
+                    log::error!("Failed to index agent flow: {}", e);
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Vec<TextSearchHit> {
+        let tokens: Vec<String> = tokenize(query).collect();
+        let Some((last, rest)) = tokens.split_last() else {
+            return Vec::new();
+        };
+
+        // All words must match, the last one as a prefix since it may still be typed.
+        let mut candidates: Option<HashSet<&DocKey>> = None;
+        for token in rest {
+            let keys: HashSet<&DocKey> = self.postings.get(token).into_iter().flatten().collect();
+            candidates = Some(match candidates {
+                Some(c) => c.intersection(&keys).copied().collect(),
+                None => keys,
+            });
+        }
+        let prefixed: HashSet<&DocKey> = self
+            .postings
+            .iter()
+            .filter(|(token, _)| token.starts_with(last.as_str()))
+            .flat_map(|(_, keys)| keys)
+            .collect();
+        let candidates = match candidates {
+            Some(c) => c.intersection(&prefixed).copied().collect(),
+            None => prefixed,
+        };
+
+        let query_lower = query.trim().to_lowercase();
+        let mut hits: Vec<TextSearchHit> = candidates
+            .into_iter()
+            .filter_map(|key| {
+                let doc = self.docs.get(key)?;
+                let text_lower = doc.text.to_lowercase();
+                // Exact phrases rank above scattered words
+                let score = text_lower.matches(&query_lower).count() * 10
+                    + tokens
+                        .iter()
+                        .map(|t| text_lower.matches(t.as_str()).count())
+                        .sum::<usize>();
+                let at = text_lower
+                    .find(&query_lower)
+                    .or_else(|| text_lower.find(last.as_str()))
+                    .unwrap_or(0);
+                Some(TextSearchHit {
+                    key: key.clone(),
+                    title: doc.title.clone(),
+                    snippet: snippet(&doc.text, &text_lower, at),
+                    score,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.key.cmp(&b.key)));
+        hits.truncate(limit);
+        hits
+    }
+}
+
+fn snippet(text: &str, text_lower: &str, at: usize) -> String {
+    // Lowercasing can change byte lengths, so convert through the char index.
+    let char_at = text_lower.get(..at).map(|s| s.chars().count()).unwrap_or(0);
+    let start = char_at.saturating_sub(SNIPPET_CONTEXT);
+    let mut snippet: String = text
+        .chars()
+        .skip(start)
+        .take(SNIPPET_CONTEXT * 2)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if text.chars().count() > start + SNIPPET_CONTEXT * 2 {
+        snippet.push('…');
+    }
+    snippet
+}
+
+impl TextIndex {
+    pub fn update_flow_file(&self, path: &Path) {
+        self.state
+            .lock()
+            .unwrap()
+            .index_flow_file(path)
+            .unwrap_or_else(|e| {
+                log::error!("Failed to index agent flow: {}", e);
+            });
+    }
+
+    pub fn update_flow(&self, flows_dir: &Path, flow_name: &str) {
+        self.update_flow_file(&flows_dir.join(format!("{}.json", flow_name)));
+    }
+
+    pub fn record_display(
+        &self,
+        app: &AppHandle,
+        agent_id: &str,
+        key: &str,
+        data: &impl Serialize,
+    ) {
+        let (flow, title) = {
+            let mut topology = self.topology.lock().unwrap();
+            topology.refresh(app);
+            let Some(flow) = topology.flow_of(agent_id).cloned() else {
+                return;
+            };
+            (flow, topology.node_name(agent_id))
+        };
+        let node = app.state::<ASApp>().file_node_id(agent_id);
+
+        let value = serde_json::to_value(data).unwrap_or_default();
+        let value = value.get("value").unwrap_or(&value);
+        let mut strings = Vec::new();
+        collect_strings(value, &mut strings);
+        let text: String = strings.join("\n").chars().take(MAX_OUTPUT_LEN).collect();
+
+        self.state.lock().unwrap().insert(
+            DocKey {
+                source: DocSource::Output,
+                flow,
+                node,
+                field: key.to_string(),
+            },
+            title,
+            text,
+        );
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<TextSearchHit> {
+        self.state.lock().unwrap().search(query, limit)
+    }
+
+    pub fn search_flows(&self, query: &str) -> BTreeMap<String, Vec<TextSearchHit>> {
+        let mut flows: BTreeMap<String, Vec<TextSearchHit>> = BTreeMap::new();
+        for hit in self.search(query, usize::MAX) {
+            flows.entry(hit.key.flow.clone()).or_default().push(hit);
+        }
+        flows
+    }
+}
+
+pub fn init(app: &AppHandle) -> Result<()> {
+    app.manage(TextIndex::default());
+    reload(app)
+}
+
+pub fn reload(app: &AppHandle) -> Result<()> {
+    let flows_dir = app.state::<ASApp>().agent_flows_dir();
+
+    let app_handle = app.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                log::error!("Flows directory watch error: {}", e);
+                return;
+            }
+        };
+        let index = app_handle.state::<TextIndex>();
+        for path in &event.paths {
+            index.update_flow_file(path);
+        }
+    })?;
+    if flows_dir.is_dir() {
+        watcher.watch(&flows_dir, RecursiveMode::Recursive)?;
+    }
+
+    let index = app.state::<TextIndex>();
+    let mut state = index.state.lock().unwrap();
+    *state = TextIndexState {
+        flows_dir: flows_dir.clone(),
+        _watcher: Some(watcher),
+        ..Default::default()
+    };
+    state.index_flows_dir(&flows_dir)?;
+    log::info!(
+        "indexed {} fields of agent flows in {:?}",
+        state.docs.len(),
+        flows_dir
+    );
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn search_text_cmd(
+    index: State<TextIndex>,
+    query: String,
+    limit: Option<usize>,
+) -> Vec<TextSearchHit> {
+    index.search(&query, limit.unwrap_or(DEFAULT_LIMIT))
+}
+
+#[tauri::command]
+pub fn search_text_flows_cmd(
+    index: State<TextIndex>,
+    query: String,
+) -> BTreeMap<String, Vec<TextSearchHit>> {
+    index.search_flows(&query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow_key(node: &str, field: &str) -> DocKey {
+        DocKey {
+            source: DocSource::Flow,
+            flow: "flow".to_string(),
+            node: node.to_string(),
+            field: field.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens: Vec<String> = tokenize("Call call_llm, then Résumé-2!").collect();
+        assert_eq!(tokens, vec!["call", "call_llm", "then", "résumé", "2"]);
+    }
+
+    #[test]
+    fn test_search() {
+        let mut state = TextIndexState::default();
+        state.insert(
+            flow_key("1", "prompt"),
+            "Summarize".to_string(),
+            "Summarize the daily notes".to_string(),
+        );
+        state.insert(
+            flow_key("2", "prompt"),
+            "Translate".to_string(),
+            "Translate the notes into French".to_string(),
+        );
+
+        let hits = state.search("notes", 10);
+        assert_eq!(hits.len(), 2);
+
+        // All words must match, the last one as a prefix
+        let hits = state.search("daily no", 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].key, flow_key("1", "prompt"));
+        assert_eq!(hits[0].title, "Summarize");
+
+        assert!(state.search("weekly notes", 10).is_empty());
+        assert!(state.search("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_search_ranks_phrases_first() {
+        let mut state = TextIndexState::default();
+        state.insert(
+            flow_key("1", "a"),
+            String::new(),
+            "notes about the daily plan".to_string(),
+        );
+        state.insert(
+            flow_key("2", "b"),
+            String::new(),
+            "the daily notes".to_string(),
+        );
+
+        let hits = state.search("daily notes", 10);
+        assert_eq!(hits[0].key, flow_key("2", "b"));
+    }
+
+    #[test]
+    fn test_insert_replaces() {
+        let mut state = TextIndexState::default();
+        state.insert(flow_key("1", "a"), String::new(), "old text".to_string());
+        state.insert(flow_key("1", "a"), String::new(), "new text".to_string());
+        assert!(state.search("old", 10).is_empty());
+        assert_eq!(state.search("new", 10).len(), 1);
+
+        state.remove(&flow_key("1", "a"));
+        assert!(state.postings.is_empty());
+    }
+}
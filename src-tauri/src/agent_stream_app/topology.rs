@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use tauri::AppHandle;
+use tauri_plugin_askit::ASKitExt;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug)]
+pub struct InEdge {
+    pub source: String,
+    pub source_handle: String,
+    pub target_handle: String,
+}
+
+/// Observer events only carry agent ids, so this is used to find
+/// which flow a node belongs to and where its inputs come from.
+#[derive(Default)]
+pub struct Topology {
+    in_edges: HashMap<String, Vec<InEdge>>,
+    flows: HashMap<String, String>,
+    titles: HashMap<String, String>,
+    updated: Option<Instant>,
+}
+
+impl Topology {
+    pub fn refresh(&mut self, app: &AppHandle) {
+        if self
+            .updated
+            .is_some_and(|updated| updated.elapsed() < REFRESH_INTERVAL)
+        {
+            return;
+        }
+
+        let mut topology = Topology {
+            updated: Some(Instant::now()),
+            ..Default::default()
+        };
+        for (flow_name, flow) in app.askit().get_agent_flows() {
+            let Ok(flow) = serde_json::to_value(&flow) else {
+                continue;
+            };
+            for node in flow
+                .get("nodes")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let Some(id) = node.get("id").and_then(Value::as_str) else {
+                    continue;
+                };
+                topology.flows.insert(id.to_string(), flow_name.clone());
+                if let Some(title) = node.get("title").and_then(Value::as_str) {
+                    topology.titles.insert(id.to_string(), title.to_string());
+                }
+            }
+            for edge in flow
+                .get("edges")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let field = |key: &str| {
+                    edge.get(key)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                let target = field("target");
+                if target.is_empty() {
+                    continue;
+                }
+                topology.in_edges.entry(target).or_default().push(InEdge {
+                    source: field("source"),
+                    source_handle: field("source_handle"),
+                    target_handle: field("target_handle"),
+                });
+            }
+        }
+        *self = topology;
+    }
+
+    pub fn flow_of(&self, agent_id: &str) -> Option<&String> {
+        self.flows.get(agent_id)
+    }
+
+    /// Ids are renamed whenever a flow is loaded, so titles are used to refer to
+    /// nodes across runs.
+    pub fn node_name(&self, agent_id: &str) -> String {
+        self.titles
+            .get(agent_id)
+            .cloned()
+            .unwrap_or_else(|| agent_id.to_string())
+    }
+
+    pub fn in_edges(&self, agent_id: &str) -> &[InEdge] {
+        self.in_edges
+            .get(agent_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context as _, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::topology::Topology;
+
+const EMIT_TRACE: &str = "asapp:trace";
+const MAX_TRACE_EVENTS: usize = 10_000;
+const MAX_SUMMARY_LEN: usize = 200;
+// Values sent from outside are dropped if the agent never reports the input
+const PENDING_INPUT_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TraceEvent {
+    pub id: u64,
+    /// The event of the source agent that produced this message.
+    pub parent: Option<u64>,
+    pub flow: String,
+    pub kind: TraceEventKind,
+    pub source: Option<String>,
+    pub source_ch: Option<String>,
+    pub target: String,
+    pub ch: String,
+    pub summary: Option<String>,
+    pub time: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceEventKind {
+    Input,
+    Display,
+    Error,
+}
+
+#[derive(Default)]
+pub struct Tracer(Mutex<TracerState>);
+
+#[derive(Default)]
+struct TracerState {
+    flows: BTreeSet<String>,
+    traces: HashMap<String, VecDeque<TraceEvent>>,
+    last_events: HashMap<String, u64>,
+    last_summaries: HashMap<String, String>,
+    pending_inputs: HashMap<(String, String), (String, Instant)>,
+    next_id: u64,
+    topology: Topology,
+}
+
+impl Tracer {
+    pub fn set_enabled(&self, flow: &str, enabled: bool) {
+        let mut state = self.0.lock().unwrap();
+        if enabled {
+            state.flows.insert(flow.to_string());
+        } else {
+            state.flows.remove(flow);
+        }
+    }
+
+    pub fn traced_flows(&self) -> Vec<String> {
+        self.0.lock().unwrap().flows.iter().cloned().collect()
+    }
+
+    pub fn expect_input(&self, app: &AppHandle, agent_id: &str, ch: &str, data: &impl Serialize) {
+        let mut state = self.0.lock().unwrap();
+        state
+            .pending_inputs
+            .retain(|_, (_, time)| time.elapsed() < PENDING_INPUT_TTL);
+        if state.traced_flow(app, agent_id).is_none() {
+            return;
+        }
+        if let Some(summary) = summarize(data) {
+            state.pending_inputs.insert(
+                (agent_id.to_string(), ch.to_string()),
+                (summary, Instant::now()),
+            );
+        }
+    }
+
+    pub fn record_input(&self, app: &AppHandle, agent_id: &str, ch: &str) {
+        let event = {
+            let mut state = self.0.lock().unwrap();
+            let Some(flow) = state.traced_flow(app, agent_id) else {
+                return;
+            };
+
+            // The input event has no payload, so show the value sent from outside,
+            // or the latest output of the source agent.
+            let pending = state
+                .pending_inputs
+                .remove(&(agent_id.to_string(), ch.to_string()))
+                .filter(|(_, time)| time.elapsed() < PENDING_INPUT_TTL)
+                .map(|(summary, _)| summary);
+
+            // The event doesn't tell the source, so among the edges into this channel
+            // take the one whose source was active last.
+            let edge = if pending.is_some() {
+                None
+            } else {
+                state
+                    .topology
+                    .in_edges(agent_id)
+                    .iter()
+                    .filter(|edge| edge.target_handle == ch)
+                    .max_by_key(|edge| state.last_events.get(&edge.source).copied())
+                    .cloned()
+            };
+            let parent = edge
+                .as_ref()
+                .and_then(|edge| state.last_events.get(&edge.source).copied());
+            let summary = pending.or_else(|| {
+                edge.as_ref()
+                    .and_then(|edge| state.last_summaries.get(&edge.source).cloned())
+            });
+
+            state.push(TraceEvent {
+                id: 0,
+                parent,
+                flow,
+                kind: TraceEventKind::Input,
+                source: edge.as_ref().map(|edge| edge.source.clone()),
+                source_ch: edge.map(|edge| edge.source_handle),
+                target: agent_id.to_string(),
+                ch: ch.to_string(),
+                summary,
+                time: Utc::now(),
+            })
+        };
+        emit_trace(app, &event);
+    }
+
+    pub fn record_display(
+        &self,
+        app: &AppHandle,
+        agent_id: &str,
+        key: &str,
+        data: &impl Serialize,
+    ) {
+        self.record_output(app, TraceEventKind::Display, agent_id, key, summarize(data));
+    }
+
+    pub fn record_error(&self, app: &AppHandle, agent_id: &str, message: &str) {
+        self.record_output(
+            app,
+            TraceEventKind::Error,
+            agent_id,
+            "",
+            Some(truncate(message)),
+        );
+    }
+
+    fn record_output(
+        &self,
+        app: &AppHandle,
+        kind: TraceEventKind,
+        agent_id: &str,
+        key: &str,
+        summary: Option<String>,
+    ) {
+        let event = {
+            let mut state = self.0.lock().unwrap();
+            let Some(flow) = state.traced_flow(app, agent_id) else {
+                return;
+            };
+            let parent = state.last_events.get(agent_id).copied();
+            if kind == TraceEventKind::Display {
+                if let Some(summary) = &summary {
+                    state
+                        .last_summaries
+                        .insert(agent_id.to_string(), summary.clone());
+                }
+            }
+            state.push(TraceEvent {
+                id: 0,
+                parent,
+                flow,
+                kind,
+                source: None,
+                source_ch: None,
+                target: agent_id.to_string(),
+                ch: key.to_string(),
+                summary,
+                time: Utc::now(),
+            })
+        };
+        emit_trace(app, &event);
+    }
+
+    pub fn events(&self, flow: &str) -> Vec<TraceEvent> {
+        let state = self.0.lock().unwrap();
+        state
+            .traces
+            .get(flow)
+            .map(|events| events.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&self, flow: &str) {
+        self.0.lock().unwrap().traces.remove(flow);
+    }
+
+    pub fn reset(&self) {
+        *self.0.lock().unwrap() = TracerState::default();
+    }
+}
+
+impl TracerState {
+    fn traced_flow(&mut self, app: &AppHandle, agent_id: &str) -> Option<String> {
+        if self.flows.is_empty() {
+            return None;
+        }
+        self.topology.refresh(app);
+        self.topology
+            .flow_of(agent_id)
+            .filter(|flow| self.flows.contains(*flow))
+            .cloned()
+    }
+
+    fn push(&mut self, mut event: TraceEvent) -> TraceEvent {
+        self.next_id += 1;
+        event.id = self.next_id;
+        self.last_events.insert(event.target.clone(), event.id);
+
+        let events = self.traces.entry(event.flow.clone()).or_default();
+        if events.len() >= MAX_TRACE_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(event.clone());
+        event
+    }
+}
+
+fn emit_trace(app: &AppHandle, event: &TraceEvent) {
+    app.emit(EMIT_TRACE, event).unwrap_or_else(|e| {
+        log::error!("Failed to emit trace event: {}", e);
+    });
+}
+
+fn summarize(data: &impl Serialize) -> Option<String> {
+    serde_json::to_string(data).ok().map(|s| truncate(&s))
+}
+
+fn truncate(s: &str) -> String {
+    super::tray::truncate(s, MAX_SUMMARY_LEN)
+}
+
+// export
+
+/// Chrome trace event format, viewable in chrome://tracing or Perfetto.
+pub fn to_chrome_trace(events: &[TraceEvent]) -> Value {
+    let mut tids: HashMap<&str, usize> = HashMap::new();
+    let mut trace_events = Vec::new();
+    let events_by_id: HashMap<u64, &TraceEvent> = events.iter().map(|e| (e.id, e)).collect();
+
+    for event in events {
+        let next_tid = tids.len() + 1;
+        let tid = *tids.entry(event.target.as_str()).or_insert(next_tid);
+        let ts = event.time.timestamp_micros();
+        trace_events.push(json!({
+            "name": format!("{:?} {}", event.kind, event.ch),
+            "cat": event.flow,
+            "ph": "i",
+            "s": "t",
+            "ts": ts,
+            "pid": 1,
+            "tid": tid,
+            "args": {
+                "id": event.id,
+                "agent_id": event.target,
+                "source": event.source,
+                "source_ch": event.source_ch,
+                "summary": event.summary,
+            },
+        }));
+
+        // Flow arrows from the parent event
+        if let Some(parent) = event.parent.and_then(|p| events_by_id.get(&p)) {
+            let parent_tid = tids.get(parent.target.as_str()).copied().unwrap_or(tid);
+            trace_events.push(json!({
+                "name": "message", "cat": event.flow, "ph": "s", "id": event.id,
+                "ts": parent.time.timestamp_micros(), "pid": 1, "tid": parent_tid,
+            }));
+            trace_events.push(json!({
+                "name": "message", "cat": event.flow, "ph": "f", "bp": "e", "id": event.id,
+                "ts": ts, "pid": 1, "tid": tid,
+            }));
+        }
+    }
+
+    // Name the threads after the agents
+    for (agent_id, tid) in &tids {
+        trace_events.push(json!({
+            "name": "thread_name", "ph": "M", "pid": 1, "tid": tid,
+            "args": { "name": agent_id },
+        }));
+    }
+
+    json!({ "traceEvents": trace_events, "displayTimeUnit": "ms" })
+}
+
+/// OpenTelemetry OTLP/JSON with one span per event, linked to the span of its parent.
+pub fn to_otel_json(flow: &str, events: &[TraceEvent]) -> Value {
+    let trace_id = format!("{:032x}", fnv1a(flow.as_bytes()) as u128);
+    let span_id = |id: u64| format!("{:016x}", id);
+
+    let spans: Vec<Value> = events
+        .iter()
+        .map(|event| {
+            let nanos = event
+                .time
+                .timestamp_nanos_opt()
+                .unwrap_or_default()
+                .to_string();
+            let mut attributes = vec![
+                otel_attr("askit.flow", &event.flow),
+                otel_attr("askit.agent_id", &event.target),
+                otel_attr("askit.ch", &event.ch),
+            ];
+            if let Some(source) = &event.source {
+                attributes.push(otel_attr("askit.source", source));
+            }
+            if let Some(source_ch) = &event.source_ch {
+                attributes.push(otel_attr("askit.source_ch", source_ch));
+            }
+            if let Some(summary) = &event.summary {
+                attributes.push(otel_attr("askit.summary", summary));
+            }
+            json!({
+                "traceId": trace_id,
+                "spanId": span_id(event.id),
+                "parentSpanId": event.parent.map(span_id).unwrap_or_default(),
+                "name": format!("{:?} {}", event.kind, event.ch),
+                "kind": 1,
+                "startTimeUnixNano": nanos,
+                "endTimeUnixNano": nanos,
+                "attributes": attributes,
+                "status": { "code": if event.kind == TraceEventKind::Error { 2 } else { 0 } },
+            })
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": { "attributes": [otel_attr("service.name", "agent-stream-app")] },
+            "scopeSpans": [{
+                "scope": { "name": "agent-stream-app" },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn otel_attr(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub fn export_trace(tracer: &Tracer, flow: &str, path: PathBuf, format: &str) -> Result<()> {
+    let events = tracer.events(flow);
+    let value = match format {
+        "json" => serde_json::to_value(&events)?,
+        "chrome" => to_chrome_trace(&events),
+        "otel" => to_otel_json(flow, &events),
+        _ => bail!("Unknown trace format: {}", format),
+    };
+    let json = serde_json::to_string_pretty(&value)?;
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(())
+}
+
+pub fn init(app: &AppHandle) -> Result<()> {
+    app.manage(Tracer::default());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_flow_trace_cmd(tracer: State<Tracer>, flow: String, enabled: bool) {
+    tracer.set_enabled(&flow, enabled);
+}
+
+#[tauri::command]
+pub fn get_traced_flows_cmd(tracer: State<Tracer>) -> Vec<String> {
+    tracer.traced_flows()
+}
+
+#[tauri::command]
+pub fn get_flow_trace_cmd(tracer: State<Tracer>, flow: String) -> Vec<TraceEvent> {
+    tracer.events(&flow)
+}
+
+#[tauri::command]
+pub fn clear_flow_trace_cmd(tracer: State<Tracer>, flow: String) {
+    tracer.clear(&flow);
+}
+
+#[tauri::command]
+pub fn export_flow_trace_cmd(
+    tracer: State<Tracer>,
+    flow: String,
+    path: PathBuf,
+    format: Option<String>,
+) -> Result<(), String> {
+    export_trace(&tracer, &flow, path, format.as_deref().unwrap_or("json"))
+        .map_err(|e| e.to_string())
+}
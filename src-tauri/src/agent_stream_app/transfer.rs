@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::{bail, Context as _, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use super::secret;
+use super::settings::{self, CoreSettings, SettingsError};
+
+const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedSettings {
+    pub version: u32,
+    #[serde(default)]
+    pub core: Option<Value>,
+    #[serde(default)]
+    pub agents: Option<Value>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ImportConflict {
+    pub key: String,
+    pub current: Value,
+    pub imported: Value,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub conflicts: Vec<ImportConflict>,
+    pub errors: BTreeMap<String, String>,
+    pub unknown_configs: BTreeMap<String, Vec<String>>,
+    pub applied: bool,
+}
+
+pub fn export_settings(app: &AppHandle, path: PathBuf, include_secrets: bool) -> Result<()> {
+    let core = {
+        let settings = app.state::<Mutex<CoreSettings>>();
+        let settings = settings.lock().unwrap();
+        serde_json::to_value(&*settings)?
+    };
+
+    let mut agents = settings::agent_global_configs_json(app)?;
+    if !include_secrets {
+        for (agent_name, key) in secret::secret_config_keys(app) {
+            if let Some(configs) = agents.get_mut(&agent_name).and_then(|c| c.as_object_mut()) {
+                configs.remove(&key);
+            }
+        }
+    }
+
+    let exported = ExportedSettings {
+        version: EXPORT_VERSION,
+        core: Some(core),
+        agents: Some(agents),
+    };
+    let json = serde_json::to_string_pretty(&exported)?;
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {:?}", path))?;
+
+    Ok(())
+}
+
+/// With `dry_run`, only the conflicts are reported.
+pub fn import_settings(app: &AppHandle, path: PathBuf, dry_run: bool) -> Result<ImportReport> {
+    let content =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    let imported: ExportedSettings =
+        serde_json::from_str(&content).context("Invalid settings file")?;
+    if imported.version > EXPORT_VERSION {
+        bail!("Unsupported settings file version: {}", imported.version);
+    }
+
+    let mut report = ImportReport::default();
+    let secret_keys = secret::secret_config_keys(app);
+
+    let current_core = {
+        let settings = app.state::<Mutex<CoreSettings>>();
+        let settings = settings.lock().unwrap();
+        settings.clone()
+    };
+    if let Some(core) = &imported.core {
+        collect_conflicts(
+            "core",
+            &serde_json::to_value(&current_core)?,
+            core,
+            &mut report.conflicts,
+        );
+    }
+
+    let current_agents = settings::agent_global_configs_json(app)?;
+    if let Some(agents) = &imported.agents {
+        for (agent_name, configs) in agents.as_object().into_iter().flatten() {
+            let Some(current_configs) = current_agents.get(agent_name) else {
+                report.errors.insert(
+                    format!("agents.{}", agent_name),
+                    "Agent not found".to_string(),
+                );
+                continue;
+            };
+            for (key, value) in configs.as_object().into_iter().flatten() {
+                let current = current_configs.get(key).cloned().unwrap_or(Value::Null);
+                if &current == value {
+                    continue;
+                }
+                let is_secret = secret_keys.contains(&(agent_name.clone(), key.clone()));
+                let mask = |v: Value| {
+                    if is_secret {
+                        Value::String(secret::SECRET_MASK.to_string())
+                    } else {
+                        v
+                    }
+                };
+                report.conflicts.push(ImportConflict {
+                    key: format!("agents.{}.{}", agent_name, key),
+                    current: mask(current),
+                    imported: mask(value.clone()),
+                });
+            }
+        }
+    }
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    // Core settings
+    if let Some(core) = imported.core {
+        let keys: Vec<String> = core
+            .as_object()
+            .map(|fields| fields.keys().cloned().collect())
+            .unwrap_or_default();
+        let (merged, errors) = settings::merge_core_settings(&current_core, core)?;
+        // Imported values replace the quarantined ones, unless they fail to validate too
+        let imported_keys: Vec<String> = keys
+            .into_iter()
+            .filter(|key| !errors.contains_key(key))
+            .collect();
+        settings::remove_from_quarantine(app, &imported_keys).unwrap_or_else(|e| {
+            log::error!("Failed to update quarantined settings: {}", e);
+        });
+        for (key, message) in errors {
+            report.errors.insert(format!("core.{}", key), message);
+        }
+        {
+            let settings = app.state::<Mutex<CoreSettings>>();
+            *settings.lock().unwrap() = merged.clone();
+        }
+        if let Err(SettingsError { fields, .. }) =
+            settings::apply_core_settings(app, &current_core, &merged)
+        {
+            for (key, message) in fields {
+                report.errors.insert(format!("core.{}", key), message);
+            }
+        }
+    }
+
+    // Agent global configs
+    if let Some(agents) = imported.agents {
+        for (agent_name, configs) in agents.as_object().into_iter().flatten() {
+            if current_agents.get(agent_name).is_none() {
+                continue;
+            }
+            match settings::set_global_configs(app, agent_name, configs) {
+                Ok(unknown_keys) => {
+                    if !unknown_keys.is_empty() {
+                        report
+                            .unknown_configs
+                            .insert(agent_name.clone(), unknown_keys);
+                    }
+                }
+                Err(e) => {
+                    report
+                        .errors
+                        .insert(format!("agents.{}", agent_name), e.to_string());
+                }
+            }
+        }
+    }
+
+    settings::save(app)?;
+    report.applied = true;
+
+    Ok(report)
+}
+
+fn collect_conflicts(
+    prefix: &str,
+    current: &Value,
+    imported: &Value,
+    out: &mut Vec<ImportConflict>,
+) {
+    match (current, imported) {
+        (Value::Object(current), Value::Object(imported)) => {
+            for (key, value) in imported {
+                let current = current.get(key).unwrap_or(&Value::Null);
+                collect_conflicts(&format!("{}.{}", prefix, key), current, value, out);
+            }
+        }
+        _ => {
+            if current != imported && !current.is_null() {
+                out.push(ImportConflict {
+                    key: prefix.to_string(),
+                    current: current.clone(),
+                    imported: imported.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn export_settings_cmd(
+    app: AppHandle,
+    path: PathBuf,
+    include_secrets: Option<bool>,
+) -> Result<(), String> {
+    export_settings(&app, path, include_secrets.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn import_settings_cmd(
+    app: AppHandle,
+    path: PathBuf,
+    dry_run: Option<bool>,
+) -> Result<ImportReport, String> {
+    import_settings(&app, path, dry_run.unwrap_or(false)).map_err(|e| e.to_string())
+}
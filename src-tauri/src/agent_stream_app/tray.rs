@@ -1,19 +1,33 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
 use anyhow::Result;
-use tauri::AppHandle;
-use tauri::{
-    menu::{MenuBuilder, MenuItemBuilder},
-    tray::TrayIconBuilder,
-};
+use tauri::menu::{CheckMenuItemBuilder, Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
+use tauri::tray::{TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Wry};
 
 use crate::agent_stream_app;
+use crate::agent_stream_app::action;
+use crate::agent_stream_app::app::ASApp;
+use crate::agent_stream_app::schedule::{ScheduleAction, Scheduler};
+use crate::agent_stream_app::settings::{CoreSettings, TrayAction};
 
-pub fn init(app: &AppHandle) -> Result<()> {
-    let quit = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
-    let show = MenuItemBuilder::with_id("show", "Show").build(app)?;
+const TRAY_ID: &str = "main";
+const FLOW_ITEM_PREFIX: &str = "flow:";
+const ACTION_ITEM_PREFIX: &str = "action:";
+const WINDOW_ITEM_PREFIX: &str = "window:";
+const CLEAR_ERRORS: &str = "clear_errors";
+const MAX_ERROR_MESSAGE_LEN: usize = 60;
+const MAX_UPCOMING_RUNS: usize = 5;
+const UPDATE_DEBOUNCE: Duration = Duration::from_millis(200);
+
+static UPDATE_PENDING: AtomicBool = AtomicBool::new(false);
 
-    let menu = MenuBuilder::new(app).items(&[&show, &quit]).build()?;
+pub fn init(app: &AppHandle) -> Result<()> {
+    let menu = build_menu(app)?;
 
-    TrayIconBuilder::new()
+    TrayIconBuilder::with_id(TRAY_ID)
         .icon(app.default_window_icon().unwrap().clone())
         .icon_as_template(true)
         .tooltip(&app.package_info().name)
@@ -25,9 +39,219 @@ pub fn init(app: &AppHandle) -> Result<()> {
             "show" => agent_stream_app::window::show_main(app).unwrap_or_else(|e| {
                 log::error!("Failed to show main window: {}", e);
             }),
-            _ => {}
+            CLEAR_ERRORS => {
+                app.state::<ASApp>().clear_errors();
+                update(app);
+            }
+            id => {
+                if let Some(flow_name) = id.strip_prefix(FLOW_ITEM_PREFIX) {
+                    toggle_flow(app, flow_name.to_string());
+                } else if let Some(index) = id.strip_prefix(ACTION_ITEM_PREFIX) {
+                    run_tray_action(app, index);
+                } else if let Some(label) = id.strip_prefix(WINDOW_ITEM_PREFIX) {
+                    agent_stream_app::window::open_popout_by_label(app, label).unwrap_or_else(
+                        |e| {
+                            log::error!("Failed to open window {}: {}", label, e);
+                        },
+                    );
+                }
+            }
+        })
+        .on_tray_icon_event(|tray, event| {
+            // Nodes can be started and stopped from the frontend through the askit plugin,
+            // so refresh the menu whenever the pointer enters the tray icon.
+            if let TrayIconEvent::Enter { .. } = event {
+                update(tray.app_handle());
+            }
         })
         .build(app)?;
 
     Ok(())
 }
+
+pub fn update(app: &AppHandle) {
+    // Coalesce bursts of calls, such as a storm of agent errors, into one rebuild.
+    if UPDATE_PENDING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(UPDATE_DEBOUNCE).await;
+        UPDATE_PENDING.store(false, Ordering::Release);
+        update_tray(&app).unwrap_or_else(|e| {
+            log::error!("Failed to update tray: {}", e);
+        });
+    });
+}
+
+fn update_tray(app: &AppHandle) -> Result<()> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+
+    let menu = build_menu(app)?;
+    tray.set_menu(Some(menu))?;
+
+    let mut tooltip = app.package_info().name.clone();
+    if let Some(asapp) = app.try_state::<ASApp>() {
+        if asapp.has_running_agent_flows() {
+            tooltip.push_str(" - running");
+        }
+        let num_errors = asapp.recent_errors().len();
+        if num_errors > 0 {
+            tooltip.push_str(&format!(" ({} errors)", num_errors));
+        }
+    }
+    tray.set_tooltip(Some(tooltip))?;
+
+    Ok(())
+}
+
+fn build_menu(app: &AppHandle) -> Result<Menu<Wry>> {
+    let show = MenuItemBuilder::with_id("show", "Show").build(app)?;
+    let quit = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
+
+    let mut menu = MenuBuilder::new(app).item(&show).separator();
+
+    // ASApp is managed after the tray is initialized.
+    if let Some(asapp) = app.try_state::<ASApp>() {
+        let flow_names = asapp.agent_flow_names();
+        for name in &flow_names {
+            let item = CheckMenuItemBuilder::with_id(format!("{}{}", FLOW_ITEM_PREFIX, name), name)
+                .checked(asapp.is_agent_flow_running(name))
+                .build(app)?;
+            menu = menu.item(&item);
+        }
+        if !flow_names.is_empty() {
+            menu = menu.separator();
+        }
+
+        let tray_actions = tray_actions(app);
+        if !tray_actions.is_empty() {
+            let mut actions_menu = SubmenuBuilder::new(app, "Actions");
+            for (i, tray_action) in tray_actions.iter().enumerate() {
+                let item = MenuItemBuilder::with_id(
+                    format!("{}{}", ACTION_ITEM_PREFIX, i),
+                    &tray_action.label,
+                )
+                .build(app)?;
+                actions_menu = actions_menu.item(&item);
+            }
+            let actions_menu = actions_menu.build()?;
+            menu = menu.item(&actions_menu);
+        }
+
+        let popout_windows = agent_stream_app::window::popout_windows(app);
+        if !popout_windows.is_empty() {
+            let mut windows_menu = SubmenuBuilder::new(app, "Windows");
+            for entry in &popout_windows {
+                let item = CheckMenuItemBuilder::with_id(
+                    format!("{}{}", WINDOW_ITEM_PREFIX, entry.window.label()),
+                    entry.window.title(),
+                )
+                .checked(entry.open)
+                .build(app)?;
+                windows_menu = windows_menu.item(&item);
+            }
+            let windows_menu = windows_menu.build()?;
+            menu = menu.item(&windows_menu);
+        }
+
+        if let Some(scheduler) = app.try_state::<Scheduler>() {
+            let upcoming = scheduler.upcoming(MAX_UPCOMING_RUNS);
+            if !upcoming.is_empty() {
+                let mut upcoming_menu = SubmenuBuilder::new(app, "Upcoming");
+                for info in &upcoming {
+                    let Some(next_run) = info.next_run else {
+                        continue;
+                    };
+                    let action = match &info.entry.action {
+                        ScheduleAction::Start => "start".to_string(),
+                        ScheduleAction::Stop => "stop".to_string(),
+                        ScheduleAction::SendToInput { node, .. } => format!("send to {}", node),
+                    };
+                    let text = format!(
+                        "{} {} {}",
+                        next_run
+                            .with_timezone(&chrono::Local)
+                            .format("%m/%d %H:%M:%S"),
+                        action,
+                        info.entry.flow
+                    );
+                    let item = MenuItemBuilder::new(text).enabled(false).build(app)?;
+                    upcoming_menu = upcoming_menu.item(&item);
+                }
+                let upcoming_menu = upcoming_menu.build()?;
+                menu = menu.item(&upcoming_menu);
+            }
+        }
+
+        let recent_errors = asapp.recent_errors();
+        let mut errors_menu = SubmenuBuilder::new(app, "Recent Errors");
+        if recent_errors.is_empty() {
+            let item = MenuItemBuilder::new("No errors")
+                .enabled(false)
+                .build(app)?;
+            errors_menu = errors_menu.item(&item);
+        } else {
+            for error in recent_errors.iter().rev() {
+                let text = format!(
+                    "{}: {}",
+                    error.agent_id,
+                    truncate(&error.message, MAX_ERROR_MESSAGE_LEN)
+                );
+                let item = MenuItemBuilder::new(text).enabled(false).build(app)?;
+                errors_menu = errors_menu.item(&item);
+            }
+            let clear = MenuItemBuilder::with_id(CLEAR_ERRORS, "Clear").build(app)?;
+            errors_menu = errors_menu.separator().item(&clear);
+        }
+        let errors_menu = errors_menu.build()?;
+        menu = menu.item(&errors_menu).separator();
+    }
+
+    let menu = menu.item(&quit).build()?;
+    Ok(menu)
+}
+
+fn toggle_flow(app: &AppHandle, flow_name: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let asapp = app.state::<ASApp>();
+        asapp
+            .toggle_agent_flow(&flow_name)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to toggle agent flow {}: {}", flow_name, e);
+            });
+        update(&app);
+    });
+}
+
+fn tray_actions(app: &AppHandle) -> Vec<TrayAction> {
+    let settings = app.state::<Mutex<CoreSettings>>();
+    let settings = settings.lock().unwrap();
+    settings.tray_actions.clone().unwrap_or_default()
+}
+
+fn run_tray_action(app: &AppHandle, index: &str) {
+    let Some(tray_action) = index
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| tray_actions(app).into_iter().nth(i))
+    else {
+        return;
+    };
+    action::run(app, &tray_action.action).unwrap_or_else(|e| {
+        log::error!("Failed to run tray action {}: {}", tray_action.label, e);
+    });
+}
+
+pub fn truncate(s: &str, max_len: usize) -> String {
+    if s.chars().count() <= max_len {
+        return s.to_string();
+    }
+    let mut truncated: String = s.chars().take(max_len).collect();
+    truncated.push('…');
+    truncated
+}
@@ -1,10 +1,115 @@
-use anyhow::Result;
-use tauri::{AppHandle, Manager};
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
 
-fn show_window(app: &AppHandle, label: &str) -> Result<()> {
+use super::{settings, tray};
+
+const MAIN_LABEL: &str = "main";
+const FLOW_LABEL_PREFIX: &str = "flow-";
+const NODE_LABEL_PREFIX: &str = "node-";
+const POPOUT_WINDOWS_KEY: &str = "popout_windows";
+const MAX_POPOUT_WINDOWS: usize = 10;
+
+const FLOW_WINDOW_SIZE: (f64, f64) = (1024.0, 768.0);
+const NODE_WINDOW_SIZE: (f64, f64) = (360.0, 240.0);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PopoutWindow {
+    Flow {
+        flow: String,
+    },
+    /// Nodes are referred to by title, since node ids change when a flow is loaded.
+    Node {
+        flow: String,
+        node: String,
+    },
+}
+
+impl PopoutWindow {
+    /// The names are hashed into the label too, since different names may sanitize the same.
+    pub fn label(&self) -> String {
+        match self {
+            PopoutWindow::Flow { flow } => format!(
+                "{}{}-{:016x}",
+                FLOW_LABEL_PREFIX,
+                sanitize_label(flow),
+                stable_hash(&[flow])
+            ),
+            PopoutWindow::Node { flow, node } => format!(
+                "{}{}-{}-{:016x}",
+                NODE_LABEL_PREFIX,
+                sanitize_label(flow),
+                sanitize_label(node),
+                stable_hash(&[flow, node])
+            ),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            PopoutWindow::Flow { flow } => flow.clone(),
+            PopoutWindow::Node { flow, node } => format!("{} / {}", flow, node),
+        }
+    }
+
+    fn route(&self) -> String {
+        match self {
+            PopoutWindow::Flow { flow } => route_with_query("/", &[("flow", flow.as_str())]),
+            PopoutWindow::Node { flow, node } => {
+                route_with_query("/node", &[("flow", flow.as_str()), ("node", node.as_str())])
+            }
+        }
+    }
+}
+
+fn route_with_query(path: &str, params: &[(&str, &str)]) -> String {
+    // Only used to encode the query
+    let mut url = tauri::Url::parse("asapp://localhost").unwrap();
+    url.set_path(path);
+    url.query_pairs_mut().extend_pairs(params);
+    format!("{}?{}", url.path(), url.query().unwrap_or_default())
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PopoutWindowEntry {
+    #[serde(flatten)]
+    pub window: PopoutWindow,
+    #[serde(default)]
+    pub open: bool,
+}
+
+fn sanitize_label(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// FNV-1a, which unlike `DefaultHasher` stays the same across builds,
+/// so that the window-state plugin finds the saved state again.
+fn stable_hash(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            // Separate the parts so that ("ab", "c") and ("a", "bc") differ
+            hash = (hash ^ 0xff).wrapping_mul(0x100000001b3);
+        }
+        for byte in part.bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+fn show_window(app: &AppHandle, label: &str, route: &str) -> Result<()> {
     if let Some(window) = app.get_webview_window(label) {
-        let mut url = window.url()?;
-        url.set_path("/");
+        let url = window.url()?.join(route)?;
         window.navigate(url)?;
         if window.is_minimized()? {
             window.unminimize()?;
@@ -18,12 +123,201 @@ fn show_window(app: &AppHandle, label: &str) -> Result<()> {
 }
 
 pub fn show_main(app: &AppHandle) -> Result<()> {
-    show_window(app, "main")
+    show_window(app, MAIN_LABEL, "/")
+}
+
+pub fn show_main_flow(app: &AppHandle, flow: &str) -> Result<()> {
+    show_window(app, MAIN_LABEL, &route_with_query("/", &[("flow", flow)]))
+}
+
+pub fn focus_main(app: &AppHandle) -> Result<()> {
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        if window.is_minimized()? {
+            window.unminimize()?;
+        }
+        window.show()?;
+        window.set_focus()?;
+    }
+    Ok(())
+}
+
+pub fn toggle_fullscreen(app: &AppHandle) -> Result<()> {
+    // The main window is used when the shortcut is pressed in another app.
+    let window = app
+        .webview_windows()
+        .into_values()
+        .find(|window| window.is_focused().unwrap_or(false))
+        .or_else(|| app.get_webview_window(MAIN_LABEL));
+    if let Some(window) = window {
+        window.set_fullscreen(!window.is_fullscreen()?)?;
+    }
+    Ok(())
+}
+
+pub fn is_main_visible(app: &AppHandle) -> Result<bool> {
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        return Ok(window.is_visible()? && !window.is_minimized()?);
+    }
+    Ok(false)
 }
 
 pub fn hide_main(app: &AppHandle) -> Result<()> {
-    if let Some(window) = app.get_webview_window("main") {
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
         window.hide()?;
     }
     Ok(())
 }
+
+pub fn is_popout(label: &str) -> bool {
+    label.starts_with(FLOW_LABEL_PREFIX) || label.starts_with(NODE_LABEL_PREFIX)
+}
+
+pub fn popout_windows(app: &AppHandle) -> Vec<PopoutWindowEntry> {
+    read_popout_windows(app).unwrap_or_else(|e| {
+        log::error!("Failed to read pop-out windows: {}", e);
+        Vec::new()
+    })
+}
+
+fn read_popout_windows(app: &AppHandle) -> Result<Vec<PopoutWindowEntry>> {
+    let store = settings::settings_store(app)?;
+    let Some(value) = store.get(POPOUT_WINDOWS_KEY) else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_value(value).context("Failed to read pop-out windows")
+}
+
+fn save_popout_windows(app: &AppHandle, entries: &[PopoutWindowEntry]) -> Result<()> {
+    let store = settings::settings_store(app)?;
+    store.set(POPOUT_WINDOWS_KEY, serde_json::to_value(entries)?);
+    Ok(())
+}
+
+fn set_popout_open(app: &AppHandle, window: &PopoutWindow, open: bool) -> Result<()> {
+    let mut entries = read_popout_windows(app)?;
+    entries.retain(|e| &e.window != window);
+    entries.insert(
+        0,
+        PopoutWindowEntry {
+            window: window.clone(),
+            open,
+        },
+    );
+    entries.truncate(MAX_POPOUT_WINDOWS);
+    save_popout_windows(app, &entries)?;
+    tray::update(app);
+    Ok(())
+}
+
+pub fn open_popout(app: &AppHandle, window: &PopoutWindow) -> Result<()> {
+    let label = window.label();
+    if app.get_webview_window(&label).is_some() {
+        show_window(app, &label, &window.route())?;
+    } else {
+        let (width, height) = match window {
+            PopoutWindow::Flow { .. } => FLOW_WINDOW_SIZE,
+            PopoutWindow::Node { .. } => NODE_WINDOW_SIZE,
+        };
+        WebviewWindowBuilder::new(app, &label, WebviewUrl::App(window.route().into()))
+            .title(window.title())
+            .inner_size(width, height)
+            .always_on_top(matches!(window, PopoutWindow::Node { .. }))
+            .build()?;
+    }
+    set_popout_open(app, window, true)
+}
+
+pub fn open_popout_by_label(app: &AppHandle, label: &str) -> Result<()> {
+    let entry = popout_windows(app)
+        .into_iter()
+        .find(|e| e.window.label() == label)
+        .with_context(|| format!("Pop-out window not found: {}", label))?;
+    open_popout(app, &entry.window)
+}
+
+pub fn popout_closed(app: &AppHandle, label: &str) {
+    let Some(entry) = popout_windows(app)
+        .into_iter()
+        .find(|e| e.window.label() == label)
+    else {
+        return;
+    };
+    set_popout_open(app, &entry.window, false).unwrap_or_else(|e| {
+        log::error!("Failed to save pop-out window {}: {}", label, e);
+    });
+}
+
+pub fn restore_popouts(app: &AppHandle) {
+    for entry in popout_windows(app).into_iter().rev() {
+        if !entry.open {
+            continue;
+        }
+        open_popout(app, &entry.window).unwrap_or_else(|e| {
+            log::error!("Failed to open window {}: {}", entry.window.label(), e);
+        });
+    }
+}
+
+pub fn reload(app: &AppHandle) {
+    for (label, window) in app.webview_windows() {
+        if is_popout(&label) {
+            // Destroy skips CloseRequested, so the window is still marked open in its profile.
+            window.destroy().unwrap_or_else(|e| {
+                log::error!("Failed to close window {}: {}", label, e);
+            });
+        }
+    }
+    restore_popouts(app);
+}
+
+#[tauri::command]
+pub fn open_flow_window_cmd(app: AppHandle, flow: String) -> Result<(), String> {
+    open_popout(&app, &PopoutWindow::Flow { flow }).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn open_node_window_cmd(app: AppHandle, flow: String, node: String) -> Result<(), String> {
+    open_popout(&app, &PopoutWindow::Node { flow, node }).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_popout_windows_cmd(app: AppHandle) -> Vec<PopoutWindowEntry> {
+    popout_windows(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(flow: &str, node: &str) -> PopoutWindow {
+        PopoutWindow::Node {
+            flow: flow.to_string(),
+            node: node.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_labels_do_not_collide() {
+        let flow_a = PopoutWindow::Flow {
+            flow: "a b".to_string(),
+        };
+        let flow_b = PopoutWindow::Flow {
+            flow: "a_b".to_string(),
+        };
+        assert_ne!(flow_a.label(), flow_b.label());
+        assert_ne!(node("x-y", "z").label(), node("x", "y-z").label());
+    }
+
+    #[test]
+    fn test_is_popout() {
+        assert!(is_popout(&node("flow", "node").label()));
+        assert!(is_popout(
+            &PopoutWindow::Flow {
+                flow: "flow".to_string()
+            }
+            .label()
+        ));
+        assert!(!is_popout(MAIN_LABEL));
+        assert!(!is_popout("quick_input"));
+    }
+}
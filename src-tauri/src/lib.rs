@@ -1,4 +1,4 @@
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 use tauri_plugin_window_state::{AppHandleExt, StateFlags};
 
 mod agent_stream_app;
@@ -6,23 +6,20 @@ mod agent_stream_app;
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .plugin(agent_stream_app::logging::builder().build())
+        .plugin(tauri_plugin_store::Builder::new().build())
+        .plugin(tauri_plugin_askit::init())
         .plugin(
-            tauri_plugin_log::Builder::new()
-                .level(log::LevelFilter::Info)
-                .level_for(
-                    "agent_stream_app_lib",
-                    if cfg!(debug_assertions) {
-                        log::LevelFilter::Debug
-                    } else {
-                        log::LevelFilter::Info
-                    },
-                )
+            tauri_plugin_window_state::Builder::new()
+                // The quick-input overlay is always centered.
+                .with_denylist(&[agent_stream_app::quick_input::QUICK_INPUT_LABEL])
                 .build(),
         )
-        .plugin(tauri_plugin_store::Builder::new().build())
-        .plugin(tauri_plugin_askit::init())
-        .plugin(tauri_plugin_window_state::Builder::new().build())
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            // Arguments and askit:// links of a second launch are run in this instance.
+            if agent_stream_app::cli::run_args(app, &args) {
+                return;
+            }
             log::info!("show main window");
             agent_stream_app::window::show_main(app).unwrap_or_else(|e| {
                 log::error!("Failed to show main window: {}", e);
@@ -30,12 +27,32 @@ pub fn run() {
         }))
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             let app_handle = app.handle().clone();
             tauri::async_runtime::block_on(async move {
+                agent_stream_app::profile::init(&app_handle).unwrap_or_else(|e| {
+                    panic!("Failed to initialize profiles: {}", e);
+                });
                 agent_stream_app::settings::init(&app_handle).unwrap_or_else(|e| {
                     panic!("Failed to initialize settings: {}", e);
                 });
+                agent_stream_app::logging::init(&app_handle);
+                agent_stream_app::metrics::init(&app_handle).unwrap_or_else(|e| {
+                    log::error!("Failed to initialize metrics: {}", e);
+                });
+                agent_stream_app::notification::init(&app_handle).unwrap_or_else(|e| {
+                    log::error!("Failed to initialize notification: {}", e);
+                });
+                agent_stream_app::debugger::init(&app_handle).unwrap_or_else(|e| {
+                    log::error!("Failed to initialize debugger: {}", e);
+                });
+                agent_stream_app::record::init(&app_handle).unwrap_or_else(|e| {
+                    log::error!("Failed to initialize recorder: {}", e);
+                });
+                agent_stream_app::trace::init(&app_handle).unwrap_or_else(|e| {
+                    log::error!("Failed to initialize trace: {}", e);
+                });
                 agent_stream_app::tray::init(&app_handle).unwrap_or_else(|e| {
                     log::error!("Failed to initialize tray: {}", e);
                     app_handle.exit(1);
@@ -44,12 +61,21 @@ pub fn run() {
                     log::error!("Failed to initialize agent: {}", e);
                     app_handle.exit(1);
                 });
+                agent_stream_app::text_index::init(&app_handle).unwrap_or_else(|e| {
+                    log::error!("Failed to initialize text index: {}", e);
+                });
+                agent_stream_app::deep_link::init(&app_handle).unwrap_or_else(|e| {
+                    log::error!("Failed to initialize deep links: {}", e);
+                });
                 agent_stream_app::settings::load_agent_global_configs(&app_handle).unwrap_or_else(
                     |e| {
                         log::error!("Failed to load agent global configs: {}", e);
                         app_handle.exit(1);
                     },
                 );
+                agent_stream_app::schedule::init(&app_handle).unwrap_or_else(|e| {
+                    log::error!("Failed to initialize scheduler: {}", e);
+                });
                 agent_stream_app::autostart::init(&app_handle).unwrap_or_else(|e| {
                     log::error!("Failed to initialize autostart: {}", e);
                 });
@@ -70,26 +96,95 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             exit_app_cmd,
+            agent_stream_app::app::get_agent_flows_cmd,
+            agent_stream_app::app::insert_agent_flow_cmd,
+            agent_stream_app::app::add_agent_flow_node_cmd,
+            agent_stream_app::app::set_agent_configs_cmd,
+            agent_stream_app::app::new_agent_flow_cmd,
             agent_stream_app::app::rename_agent_flow_cmd,
             agent_stream_app::app::remove_agent_flow_cmd,
             agent_stream_app::app::import_agent_flow_cmd,
             agent_stream_app::app::save_agent_flow_cmd,
+            agent_stream_app::app::start_agent_flow_cmd,
+            agent_stream_app::app::stop_agent_flow_cmd,
+            agent_stream_app::app::send_to_agent_cmd,
+            agent_stream_app::app::send_to_flow_input_cmd,
             agent_stream_app::settings::get_core_settings_cmd,
             agent_stream_app::settings::set_core_settings_cmd,
+            agent_stream_app::settings::get_global_configs_map_cmd,
+            agent_stream_app::settings::set_global_configs_cmd,
+            agent_stream_app::settings::reset_global_configs_cmd,
+            agent_stream_app::settings::get_stale_global_configs_cmd,
+            agent_stream_app::settings::get_core_settings_schema_cmd,
+            agent_stream_app::settings::get_quarantined_settings_cmd,
+            agent_stream_app::transfer::export_settings_cmd,
+            agent_stream_app::transfer::import_settings_cmd,
+            agent_stream_app::logging::query_logs_cmd,
+            agent_stream_app::metrics::get_metrics_cmd,
+            agent_stream_app::metrics::reset_metrics_cmd,
+            agent_stream_app::trace::set_flow_trace_cmd,
+            agent_stream_app::trace::get_traced_flows_cmd,
+            agent_stream_app::trace::get_flow_trace_cmd,
+            agent_stream_app::trace::clear_flow_trace_cmd,
+            agent_stream_app::trace::export_flow_trace_cmd,
+            agent_stream_app::debugger::set_breakpoint_cmd,
+            agent_stream_app::debugger::remove_breakpoint_cmd,
+            agent_stream_app::debugger::get_debug_state_cmd,
+            agent_stream_app::debugger::edit_pending_message_cmd,
+            agent_stream_app::debugger::debug_step_cmd,
+            agent_stream_app::debugger::debug_resume_cmd,
+            agent_stream_app::record::start_recording_cmd,
+            agent_stream_app::record::stop_recording_cmd,
+            agent_stream_app::record::is_recording_cmd,
+            agent_stream_app::record::replay_recording_cmd,
+            agent_stream_app::schedule::list_schedules_cmd,
+            agent_stream_app::schedule::set_schedule_cmd,
+            agent_stream_app::schedule::remove_schedule_cmd,
+            agent_stream_app::window::open_flow_window_cmd,
+            agent_stream_app::window::open_node_window_cmd,
+            agent_stream_app::window::list_popout_windows_cmd,
+            agent_stream_app::quick_input::get_quick_input_target_cmd,
+            agent_stream_app::quick_input::set_quick_input_target_cmd,
+            agent_stream_app::quick_input::submit_quick_input_cmd,
+            agent_stream_app::action::run_action_cmd,
+            agent_stream_app::search::search_cmd,
+            agent_stream_app::text_index::search_text_cmd,
+            agent_stream_app::text_index::search_text_flows_cmd,
+            agent_stream_app::dependency::get_dependency_report_cmd,
+            agent_stream_app::dependency::get_config_impact_cmd,
+            agent_stream_app::profile::list_profiles_cmd,
+            agent_stream_app::profile::create_profile_cmd,
+            agent_stream_app::profile::clone_profile_cmd,
+            agent_stream_app::profile::switch_profile_cmd,
+            agent_stream_app::profile::delete_profile_cmd,
         ])
         .on_window_event(|window, event| match event {
             tauri::WindowEvent::CloseRequested { api, .. } => {
+                // Pop-out windows are closed and can be reopened from the tray.
+                if agent_stream_app::window::is_popout(window.label()) {
+                    agent_stream_app::window::popout_closed(window.app_handle(), window.label());
+                    return;
+                }
                 #[cfg(not(target_os = "macos"))]
                 {
                     window.hide().unwrap();
                 }
                 #[cfg(target_os = "macos")]
                 {
-                    use tauri::Manager;
                     tauri::AppHandle::hide(window.app_handle()).unwrap();
                 }
                 api.prevent_close();
             }
+            tauri::WindowEvent::Focused(false)
+                if window.label() == agent_stream_app::quick_input::QUICK_INPUT_LABEL =>
+            {
+                agent_stream_app::quick_input::blurred(window.app_handle());
+            }
+            tauri::WindowEvent::DragDrop(event)
+                if window.label() == agent_stream_app::quick_input::QUICK_INPUT_LABEL =>
+            {
+                agent_stream_app::quick_input::drag_drop(window.app_handle(), event);
+            }
             _ => {}
         })
         .build(tauri::generate_context!())
@@ -100,7 +195,10 @@ pub fn run() {
                     agent_stream_app::app::ready(app).await.unwrap_or_else(|e| {
                         log::error!("Failed to start agents: {}", e);
                     });
+                    agent_stream_app::window::restore_popouts(app);
                     log::info!("Agent Stream App is ready.");
+                    let args: Vec<String> = std::env::args().collect();
+                    agent_stream_app::cli::run_args(app, &args);
                 });
             }
             tauri::RunEvent::Exit => {
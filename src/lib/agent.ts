@@ -6,6 +6,7 @@ import type {
   AgentConfigs,
   AgentDefaultConfigs,
   AgentDefinitions,
+  AgentDisplayConfigEntry,
   AgentDisplayConfigs,
   AgentFlow,
   AgentFlowEdge,
@@ -25,6 +26,27 @@ export async function importAgentFlow(path: string): Promise<AgentFlow> {
   return await invoke("import_agent_flow_cmd", { path });
 }
 
+// Config values come with their env and file references instead of the resolved values.
+export async function getAgentFlows(): Promise<Record<string, AgentFlow>> {
+  return await invoke("get_agent_flows_cmd");
+}
+
+export async function insertAgentFlow(agentFlow: AgentFlow): Promise<void> {
+  await invoke("insert_agent_flow_cmd", { agentFlow });
+}
+
+export async function addAgentFlowNode(flowName: string, node: AgentFlowNode): Promise<void> {
+  await invoke("add_agent_flow_node_cmd", { flowName, node });
+}
+
+export async function setAgentConfigs(agentId: string, configs: AgentConfigs): Promise<void> {
+  await invoke("set_agent_configs_cmd", { agentId, configs });
+}
+
+export async function newAgentFlow(name: string): Promise<AgentFlow> {
+  return await invoke("new_agent_flow_cmd", { name });
+}
+
 export async function renameAgentFlow(oldName: string, newName: string): Promise<string> {
   return await invoke("rename_agent_flow_cmd", { oldName, newName });
 }
@@ -37,6 +59,27 @@ export async function saveAgentFlow(agentFlow: AgentFlow): Promise<void> {
   await invoke("save_agent_flow_cmd", { agentFlow });
 }
 
+export async function startAgentFlow(name: string): Promise<void> {
+  await invoke("start_agent_flow_cmd", { name });
+}
+
+export async function stopAgentFlow(name: string): Promise<void> {
+  await invoke("stop_agent_flow_cmd", { name });
+}
+
+export async function sendToAgent(agentId: string, ch: string, value: any): Promise<void> {
+  await invoke("send_to_agent_cmd", { agentId, ch, value });
+}
+
+export async function sendToFlowInput(
+  flow: string,
+  node: string,
+  value: any,
+  ch: string = "in",
+): Promise<void> {
+  await invoke("send_to_flow_input_cmd", { flow, node, ch, value });
+}
+
 const agentDefinitionsKey = Symbol("agentDefinitions");
 
 export function setAgentDefinitionsContext(defs: AgentDefinitions): void {
@@ -276,3 +319,22 @@ export function serializeAgentFlowEdge(edge: TAgentFlowEdge): AgentFlowEdge {
     target_handle: edge.targetHandle ?? null,
   };
 }
+
+export function inferTypeForDisplay(config: AgentDisplayConfigEntry, data: any): string {
+  let ty = config.type;
+  if (ty === null || ty === "*") {
+    ty = data?.kind;
+    if (ty === null) {
+      return "object";
+    } else if (ty === "string") {
+      if (typeof data?.value === "string" && data.value.includes("\n")) {
+        ty = "text";
+      } else if (Array.isArray(data?.value)) {
+        if (data.value.some((v: any) => typeof v === "string" && v.includes("\n"))) {
+          ty = "text";
+        }
+      }
+    }
+  }
+  return ty;
+}
@@ -1,6 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 
-import type { CoreSettings } from "tauri-plugin-askit-api";
+import type { AgentConfigs, CoreSettings } from "tauri-plugin-askit-api";
 
 const isEdge = typeof navigator !== "undefined" && navigator.userAgent?.includes("Edg");
 
@@ -10,6 +10,396 @@ export async function exitApp(): Promise<void> {
   await invoke("exit_app_cmd");
 }
 
+// logs
+
+export type LogEntry = {
+  time: string;
+  level: string;
+  target: string;
+  message: string;
+};
+
+export type LogQuery = {
+  level?: string;
+  target?: string;
+  agent_id?: string;
+  since?: string;
+  until?: string;
+  limit?: number;
+};
+
+export async function queryLogs(query: LogQuery = {}): Promise<LogEntry[]> {
+  return await invoke("query_logs_cmd", { query });
+}
+
+// metrics
+
+export type LatencyStats = {
+  count: number;
+  total_ms: number;
+  mean_ms: number;
+  max_ms: number;
+  last_ms: number;
+};
+
+export type AgentMetrics = {
+  inputs: Record<string, number>;
+  displays: number;
+  errors: number;
+  latency: LatencyStats;
+};
+
+export type FlowMetrics = {
+  inputs: number;
+  displays: number;
+  errors: number;
+  latency: LatencyStats;
+  // Inputs per second since the metrics were started or reset.
+  throughput: number;
+};
+
+export type MetricsReport = {
+  agents: Record<string, AgentMetrics>;
+  flows: Record<string, FlowMetrics>;
+};
+
+export async function getMetrics(): Promise<MetricsReport> {
+  return await invoke("get_metrics_cmd");
+}
+
+export async function resetMetrics(): Promise<void> {
+  await invoke("reset_metrics_cmd");
+}
+
+// trace
+
+export type TraceEvent = {
+  id: number;
+  parent: number | null;
+  flow: string;
+  kind: "input" | "display" | "error";
+  source: string | null;
+  source_ch: string | null;
+  target: string;
+  ch: string;
+  summary: string | null;
+  time: string;
+};
+
+export type TraceFormat = "json" | "chrome" | "otel";
+
+export async function setFlowTrace(flow: string, enabled: boolean): Promise<void> {
+  await invoke("set_flow_trace_cmd", { flow, enabled });
+}
+
+export async function getTracedFlows(): Promise<string[]> {
+  return await invoke("get_traced_flows_cmd");
+}
+
+export async function getFlowTrace(flow: string): Promise<TraceEvent[]> {
+  return await invoke("get_flow_trace_cmd", { flow });
+}
+
+export async function clearFlowTrace(flow: string): Promise<void> {
+  await invoke("clear_flow_trace_cmd", { flow });
+}
+
+export async function exportFlowTrace(
+  flow: string,
+  path: string,
+  format: TraceFormat = "json",
+): Promise<void> {
+  await invoke("export_flow_trace_cmd", { flow, path, format });
+}
+
+// debugger
+
+export type Breakpoint = {
+  flow: string;
+  agent_id: string;
+  ch: string;
+};
+
+export type PendingMessage = {
+  id: number;
+  flow: string;
+  agent_id: string;
+  ch: string;
+  data: any;
+  time: string;
+};
+
+export type DebugState = {
+  breakpoints: Breakpoint[];
+  pending: PendingMessage[];
+};
+
+export type DebugEvent =
+  | { type: "paused"; message: PendingMessage }
+  | { type: "edited"; message: PendingMessage }
+  | { type: "delivered"; id: number };
+
+export async function setBreakpoint(flow: string, node: string, ch: string): Promise<void> {
+  await invoke("set_breakpoint_cmd", { flow, node, ch });
+}
+
+export async function removeBreakpoint(flow: string, node: string, ch: string): Promise<void> {
+  await invoke("remove_breakpoint_cmd", { flow, node, ch });
+}
+
+export async function getDebugState(): Promise<DebugState> {
+  return await invoke("get_debug_state_cmd");
+}
+
+export async function editPendingMessage(id: number, value: any): Promise<void> {
+  await invoke("edit_pending_message_cmd", { id, value });
+}
+
+export async function debugStep(flow: string): Promise<void> {
+  await invoke("debug_step_cmd", { flow });
+}
+
+export async function debugResume(flow: string): Promise<void> {
+  await invoke("debug_resume_cmd", { flow });
+}
+
+// record & replay
+
+export type RecordedInput = {
+  offset_ms: number;
+  node: string;
+  ch: string;
+  value: any;
+};
+
+export type RecordedDisplay = {
+  node: string;
+  key: string;
+  value: any;
+};
+
+export type Recording = {
+  flow: string;
+  inputs: RecordedInput[];
+  displays: RecordedDisplay[];
+};
+
+export type ReplayOptions = {
+  time_scale?: number;
+  settle_ms?: number;
+  golden?: string;
+  update_golden?: boolean;
+};
+
+export type DisplayMismatch = {
+  node: string;
+  key: string;
+  index: number;
+  expected: any | null;
+  actual: any | null;
+};
+
+export type ReplayReport = {
+  flow: string;
+  inputs: number;
+  expected: number;
+  actual: number;
+  mismatches: DisplayMismatch[];
+  passed: boolean;
+};
+
+export async function startRecording(flow: string): Promise<void> {
+  await invoke("start_recording_cmd", { flow });
+}
+
+export async function stopRecording(flow: string, path: string): Promise<Recording> {
+  return await invoke("stop_recording_cmd", { flow, path });
+}
+
+export async function isRecording(flow: string): Promise<boolean> {
+  return await invoke("is_recording_cmd", { flow });
+}
+
+export async function replayRecording(
+  path: string,
+  options: ReplayOptions = {},
+): Promise<ReplayReport> {
+  return await invoke("replay_recording_cmd", { path, options });
+}
+
+// schedules
+
+export type ScheduleTrigger =
+  | { type: "cron"; expr: string }
+  | { type: "interval"; seconds: number };
+
+export type ScheduleAction =
+  | { type: "start" }
+  | { type: "stop" }
+  | { type: "send_to_input"; node: string; ch?: string; value?: any };
+
+export type MissedRunPolicy = "skip" | "run_once" | "run_all";
+
+export type ScheduleEntry = {
+  id?: string;
+  flow: string;
+  trigger: ScheduleTrigger;
+  action: ScheduleAction;
+  enabled?: boolean;
+  missed?: MissedRunPolicy;
+  last_run?: string | null;
+  created_at?: string | null;
+};
+
+export type ScheduleInfo = ScheduleEntry & {
+  id: string;
+  next_run: string | null;
+};
+
+export async function listSchedules(): Promise<ScheduleInfo[]> {
+  return await invoke("list_schedules_cmd");
+}
+
+export async function setSchedule(entry: ScheduleEntry): Promise<ScheduleEntry> {
+  return await invoke("set_schedule_cmd", { entry });
+}
+
+export async function removeSchedule(id: string): Promise<void> {
+  await invoke("remove_schedule_cmd", { id });
+}
+
+// windows
+
+export type PopoutWindow =
+  | { type: "flow"; flow: string }
+  | { type: "node"; flow: string; node: string };
+
+export type PopoutWindowEntry = PopoutWindow & {
+  open: boolean;
+};
+
+export async function openFlowWindow(flow: string): Promise<void> {
+  await invoke("open_flow_window_cmd", { flow });
+}
+
+// `node` is the title of the node, or its id if it has no title.
+export async function openNodeWindow(flow: string, node: string): Promise<void> {
+  await invoke("open_node_window_cmd", { flow, node });
+}
+
+export async function listPopoutWindows(): Promise<PopoutWindowEntry[]> {
+  return await invoke("list_popout_windows_cmd");
+}
+
+// quick input
+
+export type QuickInputTarget = {
+  flow: string;
+  agent_id: string;
+  ch: string;
+  display_agent_id: string | null;
+};
+
+export async function getQuickInputTarget(): Promise<QuickInputTarget> {
+  return await invoke("get_quick_input_target_cmd");
+}
+
+export async function setQuickInputTarget(flow: string, node: string): Promise<QuickInputTarget> {
+  return await invoke("set_quick_input_target_cmd", { flow, node });
+}
+
+export async function submitQuickInput(input: { text?: string; path?: string }): Promise<void> {
+  await invoke("submit_quick_input_cmd", input);
+}
+
+// search
+
+export type SearchKind = "flow" | "agent" | "node" | "action";
+
+export type SearchAction =
+  | { type: "run"; action: Record<string, any> & { type: string } }
+  | { type: "add_agent"; def_name: string }
+  | { type: "open_node"; flow: string; node_id: string };
+
+export type SearchResult = {
+  kind: SearchKind;
+  title: string;
+  detail: string | null;
+  score: number;
+  action: SearchAction;
+};
+
+export async function search(query: string, limit?: number): Promise<SearchResult[]> {
+  return await invoke("search_cmd", { query, limit });
+}
+
+export type TextSearchHit = {
+  source: "flow" | "output";
+  flow: string;
+  node: string;
+  field: string;
+  title: string;
+  snippet: string;
+  score: number;
+};
+
+// Search node configs, scripts and the last display outputs of every flow.
+export async function searchText(query: string, limit?: number): Promise<TextSearchHit[]> {
+  return await invoke("search_text_cmd", { query, limit });
+}
+
+// Flows containing the query, such as a model name or a Rhai function.
+export async function searchTextFlows(query: string): Promise<Record<string, TextSearchHit[]>> {
+  return await invoke("search_text_flows_cmd", { query });
+}
+
+// Run an app action, such as the action of a search result.
+export async function runAction(action: Record<string, any> & { type: string }): Promise<void> {
+  await invoke("run_action_cmd", { action });
+}
+
+// dependencies
+
+export type GlobalConfigKey = {
+  agent: string;
+  key: string;
+};
+
+export type NodeRef = {
+  id: string;
+  name: string;
+  enabled: boolean;
+};
+
+export type FlowDependencies = {
+  flow: string;
+  running: boolean;
+  agents: Record<string, NodeRef[]>;
+  global_configs: GlobalConfigKey[];
+  missing_agents: string[];
+};
+
+export type DependencyReport = {
+  flows: FlowDependencies[];
+  agent_flows: Record<string, string[]>;
+  config_flows: Record<string, string[]>;
+};
+
+export type ConfigImpact = {
+  configs: GlobalConfigKey[];
+  secret: boolean;
+  flows: { flow: string; running: boolean; nodes: NodeRef[] }[];
+};
+
+export async function getDependencyReport(): Promise<DependencyReport> {
+  return await invoke("get_dependency_report_cmd");
+}
+
+// Flows affected by changing a global config. Without `agent`, every agent with `key` is checked.
+export async function getConfigImpact(key: string, agent?: string): Promise<ConfigImpact> {
+  return await invoke("get_config_impact_cmd", { agent, key });
+}
+
 // settings
 
 export async function getCoreSettings(): Promise<CoreSettings> {
@@ -19,3 +409,111 @@ export async function getCoreSettings(): Promise<CoreSettings> {
 export async function setCoreSettings(newSettings: Partial<CoreSettings>): Promise<void> {
   await invoke("set_core_settings_cmd", { newSettings });
 }
+
+// Secret values are masked in the returned map.
+export async function getGlobalConfigsMap(): Promise<Record<string, AgentConfigs>> {
+  return await invoke("get_global_configs_map_cmd");
+}
+
+// Returns the keys the agent doesn't have, which are not set.
+export async function setGlobalConfigs(
+  agentName: string,
+  configs: AgentConfigs,
+): Promise<string[]> {
+  return await invoke("set_global_configs_cmd", { agentName, configs });
+}
+
+// Reset all configs of the agent, or only `key`, to the defaults.
+export async function resetGlobalConfigs(agentName: string, key?: string): Promise<AgentConfigs> {
+  return await invoke("reset_global_configs_cmd", { agentName, key });
+}
+
+// Stored config keys that the agent definitions no longer have.
+export async function getStaleGlobalConfigs(): Promise<Record<string, string[]>> {
+  return await invoke("get_stale_global_configs_cmd");
+}
+
+export type SettingsField = {
+  key: string;
+  kind: string;
+  default: any;
+  allowed: any[] | null;
+  description: string;
+};
+
+export type SettingsError = {
+  message: string;
+  fields: Record<string, string>;
+};
+
+export function formatSettingsError(e: SettingsError | string): string {
+  if (typeof e === "string") {
+    return e;
+  }
+  const fields = Object.entries(e.fields).map(([key, message]) => `${key}: ${message}`);
+  return [e.message, ...fields].join("\n");
+}
+
+export async function getCoreSettingsSchema(): Promise<SettingsField[]> {
+  return await invoke("get_core_settings_schema_cmd");
+}
+
+export async function getQuarantinedSettings(): Promise<Record<string, any> | null> {
+  return await invoke("get_quarantined_settings_cmd");
+}
+
+// import / export
+
+export type ImportConflict = {
+  key: string;
+  current: any;
+  imported: any;
+};
+
+export type ImportReport = {
+  conflicts: ImportConflict[];
+  errors: Record<string, string>;
+  // Imported config keys the agents don't have, by agent name.
+  unknown_configs: Record<string, string[]>;
+  applied: boolean;
+};
+
+export async function exportSettings(path: string, includeSecrets = false): Promise<void> {
+  await invoke("export_settings_cmd", { path, includeSecrets });
+}
+
+export async function importSettings(path: string, dryRun = false): Promise<ImportReport> {
+  return await invoke("import_settings_cmd", { path, dryRun });
+}
+
+// profiles
+
+export type Profile = {
+  name: string;
+  flows_dir: string;
+};
+
+export type Profiles = {
+  current: string;
+  profiles: Record<string, Profile>;
+};
+
+export async function listProfiles(): Promise<Profiles> {
+  return await invoke("list_profiles_cmd");
+}
+
+export async function createProfile(name: string, flowsDir?: string): Promise<Profile> {
+  return await invoke("create_profile_cmd", { name, flowsDir });
+}
+
+export async function cloneProfile(source: string, name: string): Promise<Profile> {
+  return await invoke("clone_profile_cmd", { source, name });
+}
+
+export async function switchProfile(name: string): Promise<void> {
+  await invoke("switch_profile_cmd", { name });
+}
+
+export async function deleteProfile(name: string): Promise<void> {
+  await invoke("delete_profile_cmd", { name });
+}
@@ -1,8 +1,8 @@
-import { getAgentDefinitions, getAgentFlows, getGlobalConfigsMap } from "tauri-plugin-askit-api";
+import { getAgentDefinitions } from "tauri-plugin-askit-api";
 import type { AgentDefinitions } from "tauri-plugin-askit-api";
 
-import { deserializeAgentFlow } from "@/lib/agent";
-import { getCoreSettings } from "@/lib/utils";
+import { deserializeAgentFlow, getAgentFlows } from "@/lib/agent";
+import { getCoreSettings, getGlobalConfigsMap } from "@/lib/utils";
 
 // Tauri doesn't have a Node.js server to do proper SSR
 // so we will use adapter-static to prerender the app (SSG)